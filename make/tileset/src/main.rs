@@ -7,17 +7,25 @@ use image::{GenericImage, DynamicImage};
 use std::path::Path;
 use std::env::args;
 
-const SIZE: u32 = 480;
+const WIDTH: u32 = 480;
+const HEIGHT: u32 = 576;
 const FILES: &[&[&str]] = &[
     &["base/1.png", "base/2.png", "pit/top.png", "pit/left.png", "pit/right.png", "pit/bottom.png", "pit/center.png"],
-    &["object/rebar.png", "object/rubble.png", "pit/tl.png", "pit/tr.png", "pit/bl.png", "pit/br.png"],
-    &["unit/squaddie.png", "unit/squaddie_left.png", "unit/squaddie_back.png", "unit/squaddie_right.png", "unit/machine.png", "unit/machine_back.png"],
-    &["wall/ruin1_left.png", "wall/ruin1_top.png", "wall/ruin2_left.png", "wall/ruin2_top.png"],
-    &["bullet/regular.png", "bullet/plasma.png"],
-    &["item/squaddie_corpse.png", "item/machine_corpse.png", "item/scrap.png", "item/weapon.png", "item/ammo_clip.png", "item/bandages.png", "item/grenade.png"],
-    &["cursor/default.png", "cursor/crosshair.png", "path.png"],
+    &["object/rebar.png", "object/rubble.png", "pit/tl.png", "pit/tr.png", "pit/bl.png", "pit/br.png", "object/lamp.png"],
+    &["unit/squaddie.png", "unit/squaddie_left.png", "unit/squaddie_back.png", "unit/squaddie_right.png", "unit/machine.png", "unit/machine_back.png",
+      "unit/turret.png", "unit/turret_back.png"],
+    &["wall/brick_left.png", "wall/brick_top.png", "wall/concrete_left.png", "wall/concrete_top.png",
+      "wall/metal_sheet_left.png", "wall/metal_sheet_top.png", "wall/wooden_fence_left.png", "wall/wooden_fence_top.png"],
+    &["bullet/regular.png", "bullet/plasma.png",
+      "wall/brick_left_cracked.png", "wall/brick_top_cracked.png", "wall/concrete_left_cracked.png", "wall/concrete_top_cracked.png",
+      "wall/metal_sheet_left_cracked.png", "wall/metal_sheet_top_cracked.png", "wall/wooden_fence_left_cracked.png", "wall/wooden_fence_top_cracked.png"],
+    &["wall/door_left.png", "wall/door_top.png", "wall/window_left.png", "wall/window_top.png", "wall/low_wall_left.png", "wall/low_wall_top.png"],
+    &["item/squaddie_corpse.png", "item/machine_corpse.png", "item/scrap.png", "item/weapon.png", "item/ammo_clip.png", "item/bandages.png", "item/grenade.png",
+      "item/intel.png", "item/flare.png", "item/lit_flare.png"],
+    &["item/smoke_grenade.png", "decoration/smoke.png", "item/turret.png", "item/incendiary_grenade.png", "fire/1.png", "fire/2.png", "fire/3.png"],
+    &["cursor/default.png", "cursor/crosshair.png", "path.png", "marker/noise.png", "marker/downed.png"],
     &["decoration/left_edge.png", "decoration/right_edge.png", "decoration/skeleton.png", "decoration/skeleton_cracked.png", "decoration/rubble.png",
-      "decoration/crater.png", "explosion/1.png", "explosion/2.png", "explosion/3.png"],
+      "decoration/crater.png", "explosion/1.png", "explosion/2.png", "explosion/3.png", "decoration/scorch.png"],
     &["title.png"],
     &["button.png"],
 ];
@@ -29,7 +37,7 @@ fn main() {
     let output = args.next().unwrap();
 
     // Create the image to copy into
-    let mut base = DynamicImage::new_rgba8(SIZE, SIZE).to_rgba();
+    let mut base = DynamicImage::new_rgba8(WIDTH, HEIGHT).to_rgba();
     // Create a path from the directory string
     let path = Path::new(&directory);
     let mut y = 0;
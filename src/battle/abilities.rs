@@ -0,0 +1,118 @@
+// The special abilities that units can use
+
+use std::fmt;
+
+// What an ability is used on
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AbilityTarget {
+    // The unit using the ability
+    User,
+    // A tile within range of the unit
+    Tile(f32),
+}
+
+#[derive(Copy, Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum Ability {
+    Sprint,
+    Scan,
+    SelfDestruct,
+}
+
+impl Ability {
+    // How many extra moves sprinting gives
+    pub const SPRINT_MOVES: u16 = 15;
+    // The damage and radius of a self-destruct explosion
+    pub const SELF_DESTRUCT_EXPLOSION: (i16, f32) = (100, 3.0);
+
+    // The moves needed to use the ability
+    pub fn cost(self) -> u16 {
+        match self {
+            Ability::Sprint => 0,
+            Ability::Scan => 10,
+            Ability::SelfDestruct => 5,
+        }
+    }
+
+    // How many turns before the ability can be used again
+    pub fn cooldown(self) -> u8 {
+        match self {
+            Ability::Sprint => 3,
+            Ability::Scan => 2,
+            Ability::SelfDestruct => 0,
+        }
+    }
+
+    pub fn target(self) -> AbilityTarget {
+        match self {
+            Ability::Scan => AbilityTarget::Tile(15.0),
+            _ => AbilityTarget::User,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Ability::Sprint => "Sprint",
+            Ability::Scan => "Scan",
+            Ability::SelfDestruct => "Self-Destruct",
+        }
+    }
+}
+
+impl fmt::Display for Ability {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[test]
+fn abilities() {
+    use super::map::*;
+    use super::messages::*;
+    use super::units::*;
+
+    let mut map = Map::new(30, 30, 1.0);
+    map.units
+        .add(UnitType::Squaddie, Side::PlayerA, 0, 0, UnitFacing::Bottom);
+    map.units
+        .add(UnitType::Machine, Side::PlayerA, 10, 10, UnitFacing::Bottom);
+    map.units
+        .add(UnitType::Squaddie, Side::PlayerB, 11, 11, UnitFacing::Top);
+
+    let command = |ability, x, y| Command::UseAbility { ability, x, y };
+
+    // Sprinting should give extra moves and then need to cool down
+    let moves = map.units.get(0).unwrap().moves;
+    map.perform_command(0, command(Ability::Sprint, 0, 0), Side::PlayerA);
+    assert_eq!(
+        map.units.get(0).unwrap().moves,
+        moves + Ability::SPRINT_MOVES
+    );
+    map.perform_command(0, command(Ability::Sprint, 0, 0), Side::PlayerA);
+    assert_eq!(
+        map.units.get(0).unwrap().moves,
+        moves + Ability::SPRINT_MOVES
+    );
+
+    for _ in 0..Ability::Sprint.cooldown() {
+        map.units.get_mut(0).unwrap().cool_down();
+    }
+    assert!(map.units.get(0).unwrap().can_use_ability(Ability::Sprint));
+
+    // Scanning should reveal an area that can't be seen
+    map.tiles.update_visibility(&map.units);
+    assert!(!map.tiles.visibility_at(14, 0, Side::PlayerA).is_visible());
+    map.perform_command(0, command(Ability::Scan, 14, 0), Side::PlayerA);
+    assert!(map.tiles.visibility_at(14, 0, Side::PlayerA).is_visible());
+
+    // Until the side's next turn
+    map.end_turn(Side::PlayerA);
+    map.end_turn(Side::PlayerB);
+    assert!(!map.tiles.visibility_at(14, 0, Side::PlayerA).is_visible());
+
+    // Squaddies can't self-destruct, but machines can, taking the enemy down with them
+    map.perform_command(0, command(Ability::SelfDestruct, 0, 0), Side::PlayerA);
+    assert!(map.units.get(0).is_some());
+    map.perform_command(1, command(Ability::SelfDestruct, 10, 10), Side::PlayerA);
+    assert!(map.units.get(1).is_none());
+    assert!(map.units.get(2).unwrap().is_downed());
+}
@@ -1,6 +1,7 @@
 use super::map::*;
 use super::networking::*;
 use super::paths::*;
+use super::responses::*;
 use super::units::*;
 use error::*;
 use ord_subset::*;
@@ -48,6 +49,7 @@ pub struct AIClient {
     client: Client,
     finished_units: HashSet<u8>,
     waiting_for_response: Option<u8>,
+    confirmed_deployment: bool,
 }
 
 impl AIClient {
@@ -56,6 +58,7 @@ impl AIClient {
             client: Client::new(connection)?,
             finished_units: HashSet::new(),
             waiting_for_response: None,
+            confirmed_deployment: false,
         })
     }
 
@@ -74,6 +77,24 @@ impl AIClient {
             .ord_subset_min_by_key(|target| distance(unit.x, unit.y, target.x, target.y))
     }
 
+    // Find the most recently seen target that has gone out of sight, if any
+    fn latest_ghost(&self) -> Option<&Ghost> {
+        self.map()
+            .ghosts
+            .get(self.client.side)
+            .filter(|ghost| self.map().units.get(ghost.id).is_none())
+            .max_by_key(|ghost| ghost.turn)
+    }
+
+    // Find the most recent noise that came from somewhere out of sight, if any
+    fn latest_noise(&self) -> Option<&Noise> {
+        self.map()
+            .noises
+            .iter()
+            .filter(|noise| !self.client.visibility_at(noise.x, noise.y).is_visible())
+            .max_by_key(|noise| noise.turn)
+    }
+
     // Iterate over tiles a unit could reach
     fn reachable_tiles<'a>(&'a self, unit: &'a Unit) -> impl Iterator<Item = (usize, usize)> + 'a {
         self.map().tiles.iter().filter(move |&(x, y)| {
@@ -107,7 +128,13 @@ impl AIClient {
                 return Ok(());
             }
 
-            if self.waiting_for_response.is_none() && self.client.our_turn() {
+            // Keep the starting positions during deployment
+            if self.client.deploying() {
+                if !self.confirmed_deployment {
+                    self.client.confirm_deployment();
+                    self.confirmed_deployment = true;
+                }
+            } else if self.waiting_for_response.is_none() && self.client.our_turn() {
                 let next_unit = self.client.map.units.iter().find(|unit| {
                     unit.side == self.client.side
                         && unit.moves > 0
@@ -139,6 +166,30 @@ impl AIClient {
             }
         }
 
+        // Salvage and pick up useful items while there are no enemies around
+        if self.closest_target(unit).is_none() {
+            let items = &self.map().tiles.at(unit.x, unit.y).items;
+
+            let salvage = items.iter().position(|item| item.salvage().is_some());
+            let pickup = items.iter().position(|item| {
+                (item.heal(unit.tag) > 0 || item.ammo(unit.weapon.tag) > 0) && unit.can_carry(*item)
+            });
+
+            if unit.moves >= SALVAGE_COST {
+                if let Some(index) = salvage {
+                    self.client.salvage_item(unit.id, index);
+                    return true;
+                }
+            }
+
+            if unit.moves >= ITEM_COST {
+                if let Some(index) = pickup {
+                    self.client.pickup_item(unit.id, index);
+                    return true;
+                }
+            }
+        }
+
         let ai_move = match self.closest_target(unit) {
             Some(target) => {
                 if !unit.weapon.can_fire() {
@@ -149,7 +200,11 @@ impl AIClient {
                     AIMove::Fire(target)
                 }
             }
-            None => self.maximize_tile_search(unit),
+            None => match (self.latest_ghost(), self.latest_noise()) {
+                (Some(ghost), _) => self.pursue(unit, ghost.x, ghost.y),
+                (None, Some(noise)) => self.pursue(unit, noise.x, noise.y),
+                (None, None) => self.maximize_tile_search(unit),
+            },
         };
 
         debug!("Move: {:?}", ai_move);
@@ -189,6 +244,21 @@ impl AIClient {
         AIMove::new_walk(walk.path)
     }
 
+    // Return a path that gets as close as possible to where a target is thought to be
+    fn pursue(&self, unit: &Unit, target_x: usize, target_y: usize) -> AIMove {
+        let mut walk = Walk::new(Vec::new(), -distance(unit.x, unit.y, target_x, target_y));
+
+        // Loop through the reachable tiles
+        for (x, y) in self.reachable_tiles(unit) {
+            // If there is a path to the tile, check how close it is to the target
+            if let Some((path, _)) = self.pathfind(unit, x, y) {
+                walk.update(Walk::new(path, -distance(x, y, target_x, target_y)));
+            }
+        }
+
+        AIMove::new_walk(walk.path)
+    }
+
     // Return an path where the tiles searched is maximized
     fn maximize_tile_search(&self, unit: &Unit) -> AIMove {
         let mut walk = Walk::new(Vec::new(), 0.0);
@@ -219,13 +289,17 @@ impl AIClient {
             return 0.0;
         }
 
-        // Calculate the chance to hit
-        let chance_to_hit = chance_to_hit(x, y, target.x, target.y);
+        // Calculate the chance to hit as if the unit was on the tile, favouring tiles that flank the target
+        let mut shooter = unit.clone();
+        shooter.x = x;
+        shooter.y = y;
+        let flank = shooter.flank(&self.map().units, target.x, target.y);
+        let chance_to_hit = shooter.chance_to_hit(&self.map().tiles, target.x, target.y, flank);
 
         // Return chance to hit * times the weapon can be fired * weapon damage
         chance_to_hit
             * f32::from(unit.weapon.times_can_fire(moves))
-            * f32::from(unit.weapon.tag.damage())
+            * f32::from(flank.damage(unit.weapon.tag.damage()))
     }
 
     // Calculate the search score for a tile.
@@ -239,7 +313,14 @@ impl AIClient {
             if self
                 .map()
                 .tiles
-                .line_of_sight(x, y, tile_x, tile_y, unit.tag.sight(), unit.facing)
+                .line_of_sight(
+                    x,
+                    y,
+                    tile_x,
+                    tile_y,
+                    self.map().tiles.sight(unit, tile_x, tile_y),
+                    unit.facing,
+                )
                 .is_some()
             {
                 score += match self.client.visibility_at(tile_x, tile_y) {
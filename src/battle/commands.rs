@@ -1,14 +1,23 @@
+use super::abilities::*;
 use super::map::*;
 use super::paths::*;
 use super::responses::*;
 use super::units::*;
 
+use items::*;
 use rand::*;
 use resources::*;
 use utils::*;
 
 use std::collections::*;
 
+// The damage an explosion needs to do to blast a pit into the ground
+const PIT_DAMAGE: i16 = 100;
+// The chance of a bullet hitting a unit that's in the way of the shot
+const IN_THE_WAY_HIT_CHANCE: f32 = 0.5;
+// The damage a fire does to a unit standing in it at the end of each turn
+const FIRE_DAMAGE: i16 = 15;
+
 pub struct ServerResponses {
     player_a: Vec<Response>,
     player_b: Vec<Response>,
@@ -49,6 +58,19 @@ impl ServerResponses {
         self.push(Side::PlayerB, Response::new_state(map, Side::PlayerB));
     }
 
+    // Push an approximate noise to the sides that are in hearing range but can't see its source
+    pub fn push_noise(&mut self, map: &Map, x: usize, y: usize, tag: NoiseType) {
+        let radius = tag.radius() * map.weather.hearing_modifier();
+
+        self.push_if_predicate(Response::Noise(Noise::new(x, y, tag, map)), |side| {
+            !map.tiles.visibility_at(x, y, side).is_visible()
+                && map
+                    .units
+                    .iter()
+                    .any(|unit| unit.side == side && distance_under(unit.x, unit.y, x, y, radius))
+        });
+    }
+
     pub fn push_message(&mut self, message: String) {
         self.push_both(Response::Message(message));
     }
@@ -82,22 +104,30 @@ pub fn turn_command(
 }
 
 pub fn move_command(map: &mut Map, id: u8, path: Vec<UnitFacing>, responses: &mut ServerResponses) {
-    let side = map.units.get(id).unwrap().side;
+    let (side, stationary) = {
+        let unit = map.units.get(id).unwrap();
+        (unit.side, unit.tag.stationary())
+    };
+
+    if stationary {
+        return;
+    }
+
     let visible_enemies = VisibleEnemies::new(side, map);
 
     for facing in path {
-        let (moves, current_point) = {
+        let (moves, encumbrance, current_point) = {
             let unit = map.units.get(id).unwrap();
 
             if visible_enemies.new_enemy(unit.side, map).is_some() {
                 return;
             }
 
-            (unit.moves, PathPoint::from(unit))
+            (unit.moves, unit.encumbrance(), PathPoint::from(unit))
         };
 
         let future_point = current_point
-            .neighbours(map)
+            .neighbours(map, encumbrance)
             .into_iter()
             .map(|(point, _)| point)
             .find(|point| point.facing == facing);
@@ -117,17 +147,361 @@ pub fn move_command(map: &mut Map, id: u8, path: Vec<UnitFacing>, responses: &mu
             map.tiles.at_mut(future_point.x, future_point.y).walk_on();
         }
 
+        // Hurt the unit if it fell and stop it moving any further
+        let fall_damage = map.tiles.fall_damage(
+            current_point.x,
+            current_point.y,
+            future_point.x,
+            future_point.y,
+        );
+
+        if fall_damage > 0 {
+            let name = map.units.get(id).unwrap().name().to_string();
+            let (x, y) = (future_point.x, future_point.y);
+
+            damage_tile(map, x, y, fall_damage, None);
+            map.tiles.update_visibility(&map.units);
+
+            responses.push_if_predicate(
+                Response::Message(format!("{} fell and took {} damage", name, fall_damage)),
+                |side| map.tiles.visibility_at(x, y, side).is_visible(),
+            );
+            responses.push_and_update_state(map);
+            return;
+        }
+
         responses.push_and_update_state(map);
+        responses.push_noise(map, future_point.x, future_point.y, NoiseType::Footsteps);
         responses.push_both(Response::SoundEffect(SoundEffect::Walk));
         responses.push_both(Response::Walk(0.0));
+
+        if !turret_fire(map, id, current_point.x, current_point.y, responses) {
+            return;
+        }
     }
 }
 
+// Have the enemy turrets that a unit has just stepped into the view of fire at it, returning whether the unit survived
+fn turret_fire(
+    map: &mut Map,
+    id: u8,
+    from_x: usize,
+    from_y: usize,
+    responses: &mut ServerResponses,
+) -> bool {
+    let (x, y, side) = {
+        let unit = map.units.get(id).unwrap();
+        (unit.x, unit.y, unit.side)
+    };
+
+    let sees = |turret: &Unit, x, y| {
+        map.tiles
+            .line_of_sight(
+                turret.x,
+                turret.y,
+                x,
+                y,
+                map.tiles.sight(turret, x, y),
+                turret.facing,
+            )
+            .is_some()
+    };
+
+    let turrets: Vec<u8> = map
+        .units
+        .iter()
+        .filter(|turret| {
+            turret.tag.stationary()
+                && turret.side != side
+                && sees(turret, x, y)
+                && !sees(turret, from_x, from_y)
+        })
+        .map(|turret| turret.id)
+        .collect();
+
+    for turret in turrets {
+        if map.units.get(id).is_none() {
+            break;
+        }
+
+        fire_command(map, turret, x, y, responses);
+    }
+
+    map.units.get(id).is_some()
+}
+
 pub fn use_item_command(map: &mut Map, id: u8, item: usize, responses: &mut ServerResponses) {
     map.units.get_mut(id).unwrap().use_item(item);
     responses.push_and_update_state(map);
 }
 
+// Get an item from a unit's inventory if the target is an adjacent unit on the same side
+fn item_for_ally(map: &Map, id: u8, item: usize, target: u8) -> Option<(Item, &Unit)> {
+    let unit = map.units.get(id).unwrap();
+
+    map.units
+        .get(target)
+        .filter(|target| target.side == unit.side && unit.adjacent_to(target))
+        .and_then(|target| unit.inventory().get(item).map(|item| (*item, target)))
+}
+
+// Use an item on an adjacent ally
+pub fn use_item_on_command(
+    map: &mut Map,
+    id: u8,
+    item: usize,
+    target: u8,
+    responses: &mut ServerResponses,
+) {
+    let valid = item_for_ally(map, id, item, target)
+        .map(|(item, target)| target.needs_healing_from(item))
+        .unwrap_or(false);
+
+    if !valid {
+        return;
+    }
+
+    if let Some(item) = map.units.get_mut(id).unwrap().inventory_remove(item) {
+        map.units.get_mut(target).unwrap().heal_from(item);
+    }
+
+    responses.push_and_update_state(map);
+}
+
+// Hand an item to an adjacent ally
+pub fn give_item_command(
+    map: &mut Map,
+    id: u8,
+    item: usize,
+    target: u8,
+    responses: &mut ServerResponses,
+) {
+    let valid = item_for_ally(map, id, item, target)
+        .map(|(item, target)| target.can_carry(item))
+        .unwrap_or(false);
+
+    if !valid {
+        return;
+    }
+
+    if let Some(item) = map.units.get_mut(id).unwrap().inventory_remove(item) {
+        map.units.get_mut(target).unwrap().add_item(item);
+    }
+
+    responses.push_and_update_state(map);
+}
+
+pub fn salvage_item_command(map: &mut Map, id: u8, item: usize, responses: &mut ServerResponses) {
+    map.units
+        .get_mut(id)
+        .unwrap()
+        .salvage_item(&mut map.tiles, item);
+    responses.push_and_update_state(map);
+}
+
+// Deploy an item from a unit's inventory onto an adjacent empty tile
+pub fn deploy_item_command(
+    map: &mut Map,
+    id: u8,
+    item: usize,
+    x: usize,
+    y: usize,
+    responses: &mut ServerResponses,
+) {
+    let deployment = {
+        let unit = map.units.get(id).unwrap();
+
+        let in_reach = x < map.tiles.width()
+            && y < map.tiles.height()
+            && distance_under(unit.x, unit.y, x, y, 1.5)
+            && !map.taken(x, y)
+            && !map.tiles.at(x, y).obstacle.is_pit();
+
+        unit.inventory()
+            .get(item)
+            .and_then(|item| item.deploys())
+            .filter(|_| unit.tag == UnitType::Squaddie && in_reach)
+            .map(|tag| {
+                (
+                    tag,
+                    unit.side,
+                    UnitFacing::from_points(unit.x, unit.y, x, y),
+                )
+            })
+    };
+
+    if let Some((tag, side, facing)) = deployment {
+        if map
+            .units
+            .get_mut(id)
+            .unwrap()
+            .inventory_remove(item)
+            .is_some()
+        {
+            map.units.add(tag, side, x, y, facing);
+            map.tiles.update_visibility(&map.units);
+        }
+    }
+
+    responses.push_and_update_state(map);
+}
+
+// Use one of a unit's abilities, targeting a tile if the ability needs one
+pub fn use_ability_command(
+    map: &mut Map,
+    id: u8,
+    ability: Ability,
+    x: usize,
+    y: usize,
+    responses: &mut ServerResponses,
+) {
+    let (unit_x, unit_y, side, valid) = {
+        let unit = map.units.get(id).unwrap();
+
+        let in_range = match ability.target() {
+            AbilityTarget::User => true,
+            AbilityTarget::Tile(range) => {
+                x < map.tiles.width()
+                    && y < map.tiles.height()
+                    && distance_under(unit.x, unit.y, x, y, range)
+            }
+        };
+
+        (
+            unit.x,
+            unit.y,
+            unit.side,
+            in_range && unit.can_use_ability(ability),
+        )
+    };
+
+    if !valid {
+        return;
+    }
+
+    map.units.get_mut(id).unwrap().use_ability(ability);
+
+    match ability {
+        Ability::Sprint => map.units.get_mut(id).unwrap().moves += Ability::SPRINT_MOVES,
+        Ability::Scan => {
+            map.tiles.add_scan(side, x, y);
+            map.tiles.update_visibility(&map.units);
+        }
+        Ability::SelfDestruct => {
+            let (damage, radius) = Ability::SELF_DESTRUCT_EXPLOSION;
+            map.units.kill(&mut map.tiles, id);
+            explosion(map, unit_x, unit_y, damage, radius, Some(side), responses);
+        }
+    }
+
+    responses.push_and_update_state(map);
+}
+
+// Open or close the door between a unit and a tile next to it
+pub fn toggle_door_command(
+    map: &mut Map,
+    id: u8,
+    x: usize,
+    y: usize,
+    responses: &mut ServerResponses,
+) {
+    let (unit_x, unit_y, moves) = {
+        let unit = map.units.get(id).unwrap();
+        (unit.x, unit.y, unit.moves)
+    };
+
+    if moves >= DOOR_COST {
+        if let Some((door_x, door_y, side)) = map.tiles.door_between(unit_x, unit_y, x, y) {
+            if let Some(door) = map.tiles.at_mut(door_x, door_y).walls.get_mut(side) {
+                door.toggle();
+            }
+
+            map.units.get_mut(id).unwrap().moves -= DOOR_COST;
+            map.tiles.update_visibility(&map.units);
+        }
+    }
+
+    responses.push_and_update_state(map);
+}
+
+// Step onto an adjacent tile, pulling a downed ally onto the tile left behind
+pub fn drag_command(
+    map: &mut Map,
+    id: u8,
+    target: u8,
+    x: usize,
+    y: usize,
+    responses: &mut ServerResponses,
+) {
+    let (point, moves, encumbrance, valid) = {
+        let unit = map.units.get(id).unwrap();
+
+        let valid = map
+            .units
+            .get(target)
+            .map(|target| {
+                target.side == unit.side
+                    && target.is_downed()
+                    && unit.adjacent_to(target)
+                    && !map.tiles.wall_between(
+                        target.x,
+                        target.y,
+                        unit.x,
+                        unit.y,
+                        Passage::Movement,
+                    )
+            })
+            .unwrap_or(false);
+
+        (PathPoint::from(unit), unit.moves, unit.encumbrance(), valid)
+    };
+
+    if !valid {
+        return;
+    }
+
+    let step = point
+        .neighbours(map, encumbrance)
+        .into_iter()
+        .map(|(step, _)| step)
+        .find(|step| step.at(x, y));
+
+    let step = match step {
+        Some(ref step) if moves >= step.cost + DRAG_COST => step.clone(),
+        _ => return,
+    };
+
+    {
+        let unit = map.units.get_mut(id).unwrap();
+        unit.move_to(&step);
+        unit.moves -= DRAG_COST;
+    }
+
+    let (target_x, target_y) = {
+        let target = map.units.get(target).unwrap();
+        (target.x, target.y)
+    };
+
+    {
+        let target = map.units.get_mut(target).unwrap();
+        target.x = point.x;
+        target.y = point.y;
+    }
+
+    map.tiles.at_mut(x, y).walk_on();
+    map.tiles.update_visibility(&map.units);
+
+    responses.push_and_update_state(map);
+    responses.push_noise(map, x, y, NoiseType::Footsteps);
+    responses.push_both(Response::SoundEffect(SoundEffect::Walk));
+    responses.push_both(Response::Walk(0.0));
+
+    // Both units moved, so turrets that can now see either of them get a shot
+    if turret_fire(map, id, point.x, point.y, responses) {
+        turret_fire(map, target, target_x, target_y, responses);
+    }
+}
+
 pub fn pickup_item_command(map: &mut Map, id: u8, item: usize, responses: &mut ServerResponses) {
     map.units
         .get_mut(id)
@@ -152,15 +526,15 @@ pub fn throw_item_command(
     y: usize,
     responses: &mut ServerResponses,
 ) {
-    let item = {
-        let (item, unit_x, unit_y) = {
+    let (item, side) = {
+        let (item, unit_x, unit_y, side) = {
             let unit = map.units.get_mut(id).unwrap();
             if let Some(item) = unit.inventory_remove(item) {
                 if !distance_under(x, y, unit.x, unit.y, unit.tag.throw_distance()) {
                     return;
                 }
 
-                (item, unit.x, unit.y)
+                (item, unit.x, unit.y, unit.side)
             } else {
                 return;
             }
@@ -174,11 +548,19 @@ pub fn throw_item_command(
             },
         );
 
-        item
+        (item, side)
     };
 
     if let Some((damage, radius)) = item.as_explosive() {
-        explosion(map, x, y, damage, radius, responses);
+        explosion(map, x, y, damage, radius, Some(side), responses);
+    } else if let Some(radius) = item.as_smoke() {
+        map.tiles.add_smoke(x, y, radius);
+    } else if let Some(radius) = item.as_incendiary() {
+        map.tiles.ignite(x, y, radius);
+        map.update_light();
+    } else if let Some(light_source) = item.as_light_source() {
+        map.tiles.at_mut(x, y).light_source = Some(light_source);
+        map.update_light();
     } else {
         map.tiles.drop(x, y, item);
     }
@@ -193,62 +575,226 @@ pub fn fire_command(
     mut target_y: usize,
     responses: &mut ServerResponses,
 ) {
-    // Fire the unit's weapon and get if the bullet will hit and the damage it will do
-    let (will_hit, damage, unit_x, unit_y) = {
+    // Fire the unit's weapon and get if the bullet will hit, if it will be critical and the damage it will do
+    let flank = map
+        .units
+        .get(id)
+        .unwrap()
+        .flank(&map.units, target_x, target_y);
+
+    let (will_hit, critical, damage, unit_x, unit_y, side) = {
         let unit = map.units.get_mut(id).unwrap();
 
         if unit.fire_weapon() {
+            let will_hit =
+                unit.chance_to_hit(&map.tiles, target_x, target_y, flank) > random::<f32>();
+            let critical = will_hit
+                && unit.crit_chance(&map.tiles, target_x, target_y, flank) > random::<f32>();
+
             (
-                unit.chance_to_hit(target_x, target_y) > random::<f32>(),
-                unit.weapon.tag.damage(),
+                will_hit,
+                critical,
+                unit.weapon.tag.roll_damage(),
                 unit.x,
                 unit.y,
+                unit.side,
             )
         } else {
             return;
         }
     };
 
-    if will_hit {
-        // If the bullet will hit a wall, return a damage wall command
-        if let Some(((x, y), side)) = map.tiles.line_of_fire(unit_x, unit_y, target_x, target_y) {
-            target_x = x as usize;
-            target_y = y as usize;
+    // Work out where the bullet is going, with missed shots flying off the map
+    let (mut end_x, mut end_y) = if will_hit {
+        (target_x as f32, target_y as f32)
+    } else {
+        miss_target(unit_x, unit_y, target_x, target_y, map)
+    };
 
-            damage_wall(map, target_x, target_y, damage, side);
-        // If the bullet will hit at enemy, return a followup damage command
-        } else {
-            damage_tile(map, target_x, target_y, damage);
-        }
-    }
+    // Get the tiles the bullet passes on the way, up to any wall
+    let (path, wall) = map.tiles.line_of_fire_tiles(
+        unit_x,
+        unit_y,
+        (end_x.round() as isize, end_y.round() as isize),
+    );
 
-    // Push a bullet to the sides that can see it
+    // Any unit in the way (allies included) could be hit instead
+    let target = (target_x, target_y);
+    let in_the_way = path
+        .into_iter()
+        .take_while(|&tile| !(will_hit && tile == target))
+        .filter(|&tile| tile != target)
+        .find(|&(x, y)| map.units.at(x, y).is_some() && IN_THE_WAY_HIT_CHANCE > random::<f32>());
 
-    {
-        let unit = map.units.get(id).unwrap();
-        responses.push_both(Response::SoundEffect(unit.weapon.tag.fire_sound()));
+    if let Some((x, y)) = in_the_way {
+        target_x = x;
+        target_y = y;
+
+        damage_tile(map, x, y, damage, Some(side));
+    // Otherwise if the bullet will hit a wall, damage it
+    } else if let Some(((x, y), wall_side)) = wall {
+        target_x = x as usize;
+        target_y = y as usize;
+
+        damage_wall(map, target_x, target_y, damage, wall_side);
+    // If the bullet will hit the target, damage it
+    } else if critical {
+        let (name, damage) = {
+            let unit = map.units.get(id).unwrap();
+            (
+                unit.name().to_string(),
+                flank.damage(unit.weapon.tag.critical_damage()),
+            )
+        };
+
+        damage_tile(map, target_x, target_y, damage, Some(side));
 
         responses.push_if_predicate(
-            Response::new_bullet(unit, target_x, target_y, will_hit, map),
+            Response::Message(format!(
+                "{} scored a critical hit for {} damage",
+                name, damage
+            )),
             |side| {
-                map.tiles.visibility_at(unit.x, unit.y, side).is_visible()
+                map.tiles.visibility_at(unit_x, unit_y, side).is_visible()
                     || map
                         .tiles
                         .visibility_at(target_x, target_y, side)
                         .is_visible()
             },
         );
+    } else if will_hit {
+        damage_tile(map, target_x, target_y, flank.damage(damage), Some(side));
+    }
+
+    // Stop the bullet where it hit something
+    if in_the_way.is_some() || wall.is_some() {
+        end_x = target_x as f32;
+        end_y = target_y as f32;
+    }
+
+    // Push a bullet to the sides that can see it
+
+    {
+        let unit = map.units.get(id).unwrap();
+        responses.push_both(Response::SoundEffect(unit.weapon.tag.fire_sound()));
+        responses.push_noise(map, unit.x, unit.y, NoiseType::Gunfire);
+
+        responses.push_if_predicate(Response::new_bullet(unit, end_x, end_y), |side| {
+            map.tiles.visibility_at(unit.x, unit.y, side).is_visible()
+                || map
+                    .tiles
+                    .visibility_at(target_x, target_y, side)
+                    .is_visible()
+        });
     }
 
     responses.push_and_update_state(map);
 }
 
+// Bleed the downed units on a side, killing those that run out of turns
+pub fn bleed_out(map: &mut Map, side: Side, responses: &mut ServerResponses) {
+    let bled_out: Vec<_> = map
+        .units
+        .iter_mut()
+        .filter(|unit| unit.side == side)
+        .filter_map(|unit| {
+            if unit.bleed() {
+                Some((unit.id, unit.x, unit.y, unit.name().to_string()))
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    for (id, x, y, name) in bled_out {
+        map.units.kill(&mut map.tiles, id);
+
+        responses.push_if_predicate(Response::Message(format!("{} bled out", name)), |other| {
+            other == side || map.tiles.visibility_at(x, y, other).is_visible()
+        });
+    }
+}
+
+// Burn the units and items caught in fires, and spread the fires to flammable tiles nearby
+pub fn burn_fires(map: &mut Map, responses: &mut ServerResponses) {
+    let spreading = map.tiles.spreading_fire();
+
+    for (x, y) in map.tiles.iter() {
+        if !map.tiles.at(x, y).is_burning() {
+            continue;
+        }
+
+        map.tiles.at_mut(x, y).burn();
+
+        if let Some(name) = map.units.at(x, y).map(|unit| unit.name().to_string()) {
+            damage_tile(map, x, y, FIRE_DAMAGE, None);
+
+            responses.push_if_predicate(
+                Response::Message(format!("{} burned for {} damage", name, FIRE_DAMAGE)),
+                |side| map.tiles.visibility_at(x, y, side).is_visible(),
+            );
+        }
+    }
+
+    for (x, y) in spreading {
+        map.tiles.at_mut(x, y).ignite();
+    }
+
+    map.update_light();
+}
+
+// Burn down the fuses of all the primed grenades on the map and set off the ones that run out
+pub fn detonate_grenades(map: &mut Map, responses: &mut ServerResponses) {
+    let mut detonations = Vec::new();
+
+    for (x, y) in map.tiles.iter() {
+        if burn_fuses(&mut map.tiles.at_mut(x, y).items) {
+            detonations.push((x, y, None));
+        }
+    }
+
+    for unit in map.units.iter_mut() {
+        if unit.burn_fuses() {
+            detonations.push((unit.x, unit.y, Some(unit.side)));
+        }
+    }
+
+    let fuse_run_out = |item: &Item| *item == Item::Grenade(Some(0));
+
+    for (x, y, side) in detonations {
+        let grenades = match side {
+            Some(_) => match map.units.at_mut(x, y) {
+                Some(unit) => unit.take_items(fuse_run_out),
+                None => continue,
+            },
+            None => take_items(&mut map.tiles.at_mut(x, y).items, fuse_run_out),
+        };
+
+        if let Some((damage, radius)) = combine_explosives(grenades) {
+            explosion(map, x, y, damage, radius, side, responses);
+        }
+    }
+}
+
+// Grenades that go off together make one larger explosion
+fn combine_explosives(items: Vec<Item>) -> Option<(i16, f32)> {
+    items
+        .into_iter()
+        .filter_map(Item::as_explosive)
+        .fold(None, |combined, (damage, radius)| match combined {
+            Some((total, largest)) => Some((total + damage, largest.max(radius) + 0.5)),
+            None => Some((damage, radius)),
+        })
+}
+
+// Cause an explosion, with the damage being dealt by a particular side (if any)
 fn explosion(
     map: &mut Map,
     x: usize,
     y: usize,
     damage: i16,
     radius: f32,
+    side: Option<Side>,
     responses: &mut ServerResponses,
 ) {
     let affected_tiles: HashSet<_> = map
@@ -267,30 +813,69 @@ fn explosion(
         );
     }
 
+    responses.push_noise(map, x, y, NoiseType::Explosion);
+
     for &(x, y) in &affected_tiles {
-        damage_tile(map, x, y, damage);
+        damage_tile(map, x, y, damage, side);
 
-        if !map.tiles.horizontal_clear(x, y) && (x == 0 || affected_tiles.contains(&(x - 1, y))) {
+        if map.tiles.at(x, y).walls.left.is_some()
+            && (x == 0 || affected_tiles.contains(&(x - 1, y)))
+        {
             damage_wall(map, x, y, damage, WallSide::Left);
         }
 
-        if !map.tiles.vertical_clear(x, y) && (y == 0 || affected_tiles.contains(&(x, y - 1))) {
+        if map.tiles.at(x, y).walls.top.is_some()
+            && (y == 0 || affected_tiles.contains(&(x, y - 1)))
+        {
             damage_wall(map, x, y, damage, WallSide::Top);
         }
     }
+
+    // Large explosions blast a pit into the ground
+    if damage >= PIT_DAMAGE && map.tiles.blast_pit(x, y, &map.units) {
+        map.update_light();
+        map.tiles.update_visibility(&map.units);
+    }
+
+    // Set off any primed grenades caught in the explosion, including those carried by units
+    let explosive = |item: &Item| item.as_explosive().is_some();
+
+    for &(x, y) in &affected_tiles {
+        let mut grenades = take_items(&mut map.tiles.at_mut(x, y).items, explosive);
+
+        if let Some(unit) = map.units.at_mut(x, y) {
+            grenades.append(&mut unit.take_items(explosive));
+        }
+
+        if let Some((damage, radius)) = combine_explosives(grenades) {
+            explosion(map, x, y, damage, radius, side, responses);
+        }
+    }
 }
 
-fn damage_tile(map: &mut Map, x: usize, y: usize, damage: i16) {
+// Damage a tile, with the damage being dealt by a particular side (if any)
+fn damage_tile(map: &mut Map, x: usize, y: usize, damage: i16, side: Option<Side>) {
     // Deal damage to the unit and get whether it is lethal
-    if let Some((id, lethal)) = map
+    if let Some((id, unit_side, lethal)) = map
         .units
         .at_mut(x, y)
-        .map(|unit| (unit.id, unit.damage(damage)))
+        .map(|unit| (unit.id, unit.side, unit.damage(damage)))
     {
+        // Only damage to enemies counts towards the score
+        if let Some(side) = side.filter(|side| *side != unit_side) {
+            map.scores.get_mut(side).record_damage(damage, lethal);
+        }
+
         // If the damage is lethal, kill the unit
         if lethal {
             map.units.kill(&mut map.tiles, id);
         }
+    } else if map.tiles.at(x, y).obstacle.is_object() {
+        // Break the object, updating what can be seen through it
+        if map.tiles.at_mut(x, y).damage_object(damage) {
+            map.update_light();
+            map.tiles.update_visibility(&map.units);
+        }
     } else {
         // Decorate the area with a crater
         map.tiles.at_mut(x, y).decoration = Some(Image::Crater);
@@ -300,17 +885,492 @@ fn damage_tile(map: &mut Map, x: usize, y: usize, damage: i16) {
 fn damage_wall(map: &mut Map, x: usize, y: usize, damage: i16, side: WallSide) {
     let walls = &mut map.tiles.at_mut(x, y).walls;
 
-    let destroyed = match side {
-        WallSide::Left => walls.left.as_mut(),
-        WallSide::Top => walls.top.as_mut(),
-    }
-    .map(|wall| wall.damage(damage))
-    .unwrap_or(false);
+    let destroyed = walls
+        .get_mut(side)
+        .map(|wall| wall.damage(damage))
+        .unwrap_or(false);
 
     if destroyed {
         match side {
             WallSide::Left => walls.left = None,
             WallSide::Top => walls.top = None,
         }
+
+        // Leave the rubble of the wall behind
+        let tile = map.tiles.at_mut(x, y);
+        if !tile.obstacle.is_pit() {
+            tile.decoration = Some(Image::Rubble);
+        }
+
+        map.tiles.update_visibility(&map.units);
+    }
+}
+
+#[test]
+fn hearing() {
+    let mut map = Map::new(30, 30, 1.0);
+    map.units
+        .add(UnitType::Squaddie, Side::PlayerA, 0, 0, UnitFacing::Bottom);
+    map.units
+        .add(UnitType::Squaddie, Side::PlayerB, 29, 29, UnitFacing::Top);
+    map.tiles.update_visibility(&map.units);
+
+    // Gunfire out of sight should only be heard by the side in range
+
+    let mut responses = ServerResponses::new();
+    responses.push_noise(&map, 10, 10, NoiseType::Gunfire);
+    let (player_a_responses, player_b_responses) = responses.split();
+
+    assert_eq!(player_a_responses.len(), 1);
+    assert!(player_b_responses.is_empty());
+
+    if let Response::Noise(ref noise) = player_a_responses[0] {
+        // The position is only approximate
+        let accuracy = Noise::ACCURACY as isize;
+        assert!((noise.x as isize - 10).abs() <= accuracy);
+        assert!((noise.y as isize - 10).abs() <= accuracy);
+        assert_eq!(noise.direction(Side::PlayerA, &map), "to the south");
+    } else {
+        panic!("Expected a noise, got {:?}", player_a_responses[0]);
+    }
+}
+
+#[test]
+fn grenade_fuses() {
+    let mut map = Map::new(30, 30, 1.0);
+    map.units
+        .add(UnitType::Squaddie, Side::PlayerA, 0, 0, UnitFacing::Bottom);
+    map.units
+        .add(UnitType::Squaddie, Side::PlayerB, 10, 10, UnitFacing::Top);
+
+    // A primed grenade on the ground next to an unprimed one and another primed one
+
+    map.tiles.drop(10, 11, Item::Grenade(Some(2)));
+    map.tiles.drop(10, 11, Item::Grenade(None));
+    map.tiles.drop(12, 11, Item::Grenade(Some(GRENADE_FUSE)));
+
+    let mut responses = ServerResponses::new();
+    detonate_grenades(&mut map, &mut responses);
+    assert_eq!(map.tiles.at(10, 11).items.len(), 2);
+
+    // Once the fuse runs out, it should go off and set off the other primed grenade
+
+    detonate_grenades(&mut map, &mut responses);
+    assert_eq!(map.tiles.at(10, 11).items, vec![Item::Grenade(None)]);
+    assert!(map.tiles.at(12, 11).items.is_empty());
+    assert!(map.units.at(10, 10).unwrap().is_downed());
+}
+
+#[test]
+fn help_ally() {
+    let mut map = Map::new(30, 30, 1.0);
+    map.units
+        .add(UnitType::Squaddie, Side::PlayerA, 0, 0, UnitFacing::Bottom);
+    map.units
+        .add(UnitType::Squaddie, Side::PlayerA, 1, 1, UnitFacing::Bottom);
+    map.units.get_mut(1).unwrap().damage(25);
+
+    let bandages = map
+        .units
+        .get(0)
+        .unwrap()
+        .inventory()
+        .iter()
+        .position(|item| *item == Item::Bandages)
+        .unwrap();
+
+    // Bandage the wounded ally and hand them a clip
+
+    let mut responses = ServerResponses::new();
+    use_item_on_command(&mut map, 0, bandages, 1, &mut responses);
+    give_item_command(&mut map, 0, 0, 1, &mut responses);
+
+    let unit = map.units.get(0).unwrap();
+    let ally = map.units.get(1).unwrap();
+    assert_eq!(unit.moves, UnitType::Squaddie.moves() - ITEM_COST * 2);
+    assert!(!unit.inventory().contains(&Item::Bandages));
+    assert!(!ally.needs_healing_from(Item::Bandages));
+    assert_eq!(ally.inventory().len(), unit.inventory().len() + 3);
+
+    // Units that aren't adjacent can't be helped
+
+    map.units.get_mut(1).unwrap().x = 5;
+    give_item_command(&mut map, 0, 0, 1, &mut responses);
+    assert_eq!(
+        map.units.get(0).unwrap().moves,
+        UnitType::Squaddie.moves() - ITEM_COST * 2
+    );
+}
+
+#[test]
+fn destruction() {
+    let mut map = Map::new(30, 30, 1.0);
+    map.tiles.at_mut(5, 6).obstacle = Obstacle::Object(Object::new(Image::ObjectRebar));
+    map.tiles
+        .add_left_wall(6, 5, WallType::Ruin1, WallMaterial::Brick);
+    assert!(map.taken(5, 6));
+
+    // Two grenades going off together make a large explosion
+    let grenades = vec![Item::Grenade(Some(0)); 2];
+    let (damage, radius) = combine_explosives(grenades).unwrap();
+    assert!(damage >= PIT_DAMAGE);
+
+    explosion(
+        &mut map,
+        5,
+        5,
+        damage,
+        radius,
+        None,
+        &mut ServerResponses::new(),
+    );
+
+    // The object and wall should be destroyed and leave rubble
+    assert!(!map.taken(5, 6));
+    assert_eq!(map.tiles.at(5, 6).decoration, Some(Image::Rubble));
+    assert!(map.tiles.at(6, 5).walls.left.is_none());
+    assert_eq!(map.tiles.at(6, 5).decoration, Some(Image::Rubble));
+
+    // And a pit should be left where the grenades went off
+    assert!(map.tiles.at(5, 5).obstacle.is_pit());
+    assert_eq!(map.tiles.fall_damage(5, 4, 5, 5), Unit::FALL_DAMAGE);
+}
+
+#[test]
+fn turrets() {
+    use weapons::WeaponType;
+
+    let mut map = Map::new(30, 30, 1.0);
+    map.units
+        .add(UnitType::Squaddie, Side::PlayerA, 0, 0, UnitFacing::Bottom);
+    map.units
+        .add(UnitType::Squaddie, Side::PlayerB, 3, 3, UnitFacing::Top);
+    map.units.get_mut(0).unwrap().add_item(Item::Turret);
+    let item = map.units.get(0).unwrap().inventory().len() - 1;
+
+    // Deploying a turret should create a stationary unit that doesn't count towards the side
+    deploy_item_command(&mut map, 0, item, 1, 1, &mut ServerResponses::new());
+
+    let turret = map.units.at(1, 1).unwrap().id;
+    assert!(map.units.get(turret).unwrap().tag.stationary());
+    assert_eq!(map.units.count(Side::PlayerA), 1);
+    assert!(pathfind(map.units.get(turret).unwrap(), 2, 2, &map).is_none());
+
+    // It should fire at enemies that come into view until it runs out of moves
+    let shots = UnitType::Turret.moves() / WeaponType::MachineGun.cost();
+
+    for _ in 0..shots + 1 {
+        assert!(turret_fire(
+            &mut map,
+            1,
+            29,
+            29,
+            &mut ServerResponses::new()
+        ));
+    }
+
+    let ammo = WeaponType::MachineGun.capacity() - map.units.get(turret).unwrap().weapon.ammo;
+    assert_eq!(u16::from(ammo), shots);
+
+    // But not at enemies moving around inside its view
+    map.units.get_mut(turret).unwrap().moves = UnitType::Turret.moves();
+    assert!(turret_fire(&mut map, 1, 3, 2, &mut ServerResponses::new()));
+    let ammo = WeaponType::MachineGun.capacity() - map.units.get(turret).unwrap().weapon.ammo;
+    assert_eq!(u16::from(ammo), shots);
+
+    // And leave scrap when destroyed
+    map.units.kill(&mut map.tiles, turret);
+    assert!(map.tiles.at(1, 1).items.contains(&Item::Scrap));
+}
+
+#[test]
+fn friendly_fire() {
+    let mut map = Map::new(30, 30, 1.0);
+    map.units
+        .add(UnitType::Squaddie, Side::PlayerA, 0, 0, UnitFacing::Bottom);
+    map.units
+        .add(UnitType::Squaddie, Side::PlayerA, 0, 3, UnitFacing::Bottom);
+    map.units
+        .add(UnitType::Machine, Side::PlayerB, 0, 6, UnitFacing::Top);
+
+    // Shooting past an ally should hit them sooner or later
+    for _ in 0..500 {
+        if map.units.get(1).is_none() {
+            break;
+        }
+
+        {
+            let unit = map.units.get_mut(0).unwrap();
+            unit.moves = unit.tag.moves();
+            unit.weapon.ammo = unit.weapon.tag.capacity();
+        }
+
+        fire_command(&mut map, 0, 0, 6, &mut ServerResponses::new());
+    }
+
+    assert!(map.units.get(1).is_none());
+}
+
+#[test]
+fn downed_units() {
+    let mut map = Map::new(30, 30, 1.0);
+    map.units
+        .add(UnitType::Squaddie, Side::PlayerA, 0, 0, UnitFacing::Bottom);
+    map.units
+        .add(UnitType::Squaddie, Side::PlayerA, 1, 1, UnitFacing::Bottom);
+    map.units
+        .add(UnitType::Squaddie, Side::PlayerA, 5, 5, UnitFacing::Bottom);
+    map.units
+        .add(UnitType::Squaddie, Side::PlayerB, 20, 20, UnitFacing::Top);
+
+    // Lethal damage should take squaddies down instead of killing them
+    let mut responses = ServerResponses::new();
+    damage_tile(&mut map, 1, 1, 500, None);
+    damage_tile(&mut map, 5, 5, 500, None);
+    assert_eq!(map.units.count(Side::PlayerA), 3);
+    assert!(map.units.get(1).unwrap().is_downed());
+
+    // Downed allies can be dragged along
+    drag_command(&mut map, 0, 1, 0, 1, &mut responses);
+    assert_eq!(
+        (map.units.get(1).unwrap().x, map.units.get(1).unwrap().y),
+        (0, 0)
+    );
+
+    // But not through walls
+    map.tiles
+        .add_top_wall(5, 5, WallType::Ruin1, WallMaterial::Brick);
+    map.units
+        .add(UnitType::Squaddie, Side::PlayerA, 5, 4, UnitFacing::Top);
+    drag_command(&mut map, 4, 2, 5, 3, &mut responses);
+    assert_eq!(
+        (map.units.get(2).unwrap().x, map.units.get(2).unwrap().y),
+        (5, 5)
+    );
+    assert_eq!(map.units.get(4).unwrap().y, 4);
+
+    // And stabilised with bandages
+    let bandages = map
+        .units
+        .get(0)
+        .unwrap()
+        .inventory()
+        .iter()
+        .position(|item| *item == Item::Bandages)
+        .unwrap();
+    use_item_on_command(&mut map, 0, bandages, 1, &mut responses);
+
+    // So that they don't bleed out like those left alone
+    for _ in 0..BLEED_OUT_TURNS {
+        map.end_turn(Side::PlayerA);
+        map.end_turn(Side::PlayerB);
+    }
+    assert!(map.units.get(1).is_some());
+    assert!(map.units.get(2).is_none());
+    assert_eq!(map.units.get(1).unwrap().moves, 0);
+}
+
+#[test]
+fn fire() {
+    let mut map = Map::new(30, 30, 1.0);
+    map.units
+        .add(UnitType::Squaddie, Side::PlayerA, 5, 5, UnitFacing::Bottom);
+    map.units
+        .add(UnitType::Squaddie, Side::PlayerB, 10, 10, UnitFacing::Top);
+    map.units
+        .get_mut(0)
+        .unwrap()
+        .add_item(Item::IncendiaryGrenade);
+    let incendiary = map.units.get(0).unwrap().inventory().len() - 1;
+
+    map.tiles.drop(11, 11, Item::Bandages);
+    map.tiles.drop(12, 12, Item::Scrap);
+    map.tiles.at_mut(12, 11).decoration = Some(Image::Rubble);
+    map.tiles.at_mut(13, 11).decoration = Some(Image::Rubble);
+    map.tiles.at_mut(14, 11).decoration = Some(Image::Rubble);
+    map.tiles
+        .add_left_wall(14, 11, WallType::Ruin1, WallMaterial::Brick);
+    map.tiles.drop(11, 10, Item::Intel);
+
+    // An incendiary grenade should set the area alight
+    let mut responses = ServerResponses::new();
+    throw_item_command(&mut map, 0, incendiary, 10, 10, &mut responses);
+    assert!(map.tiles.at(10, 10).is_burning());
+    assert!(!map.tiles.at(12, 11).is_burning());
+
+    // Which burns the units and items caught in it, and spreads to flammable tiles nearby
+    let (_, player_b_responses) = map.end_turn(Side::PlayerA).split();
+    let burned = format!(
+        "{} burned for {} damage",
+        map.units.get(1).unwrap().name(),
+        FIRE_DAMAGE
+    );
+    assert!(player_b_responses.contains(&Response::Message(burned)));
+    assert!(map.tiles.at(11, 11).items.is_empty());
+    assert_eq!(map.tiles.at(11, 10).items, vec![Item::Intel]);
+    assert!(map.tiles.at(12, 11).is_burning());
+    assert!(!map.tiles.at(12, 12).is_burning());
+
+    // Until it runs out of things to burn
+    for _ in 0..4 {
+        map.end_turn(map.side);
+    }
+    assert!(map.tiles.at(13, 11).decoration == Some(Image::Scorch));
+    assert!(map.tiles.at(14, 11).decoration == Some(Image::Rubble));
+    assert!(map
+        .tiles
+        .iter()
+        .all(|(x, y)| !map.tiles.at(x, y).is_burning()));
+}
+
+#[test]
+fn flanking() {
+    // Shots from behind or to the side of where a unit is facing should flank it
+    let facing = UnitFacing::Bottom;
+    assert_eq!(facing.flank(5, 5, 6, 6), Flank::Front);
+    assert_eq!(facing.flank(5, 5, 5, 6), Flank::Front);
+    assert_eq!(facing.flank(5, 5, 4, 6), Flank::Side);
+    assert_eq!(facing.flank(5, 5, 4, 4), Flank::Rear);
+    assert_eq!(facing.flank(5, 5, 6, 4), Flank::Side);
+
+    // Making critical hits more likely
+    let tiles = Tiles::new(30, 30);
+    assert!(
+        tiles.crit_chance(3, 1, 0, 1, Flank::Rear) > tiles.crit_chance(3, 1, 0, 1, Flank::Side)
+    );
+    assert!(
+        tiles.crit_chance(3, 1, 0, 1, Flank::Side) > tiles.crit_chance(3, 1, 0, 1, Flank::Front)
+    );
+
+    // And hits do more damage
+    let mut map = Map::new(30, 30, 1.0);
+    map.units
+        .add(UnitType::Squaddie, Side::PlayerA, 0, 0, UnitFacing::Bottom);
+    map.units
+        .add(UnitType::Machine, Side::PlayerB, 0, 3, UnitFacing::Bottom);
+    assert_eq!(
+        map.units.get(0).unwrap().flank(&map.units, 0, 3),
+        Flank::Rear
+    );
+
+    let unharmed = map.units.get(1).unwrap().clone();
+
+    for _ in 0..500 {
+        if *map.units.get(1).unwrap() != unharmed {
+            break;
+        }
+
+        {
+            let unit = map.units.get_mut(0).unwrap();
+            unit.moves = unit.tag.moves();
+            unit.weapon.ammo = unit.weapon.tag.capacity();
+        }
+
+        fire_command(&mut map, 0, 0, 3, &mut ServerResponses::new());
     }
+
+    let weapon = map.units.get(0).unwrap().weapon.tag;
+    let (low, high) = (
+        Flank::Rear.damage(weapon.damage_range().0),
+        Flank::Rear.damage(weapon.critical_damage()),
+    );
+    assert!((low..high + 1).any(|damage| {
+        let mut unit = unharmed.clone();
+        unit.damage(damage);
+        unit == *map.units.get(1).unwrap()
+    }));
+}
+
+#[test]
+fn encumbrance() {
+    let mut map = Map::new(30, 30, 1.0);
+    map.units
+        .add(UnitType::Squaddie, Side::PlayerA, 0, 0, UnitFacing::Bottom);
+    let capacity = UnitType::Squaddie.capacity();
+
+    {
+        let unit = map.units.get_mut(0).unwrap();
+
+        // Units aren't slowed down by carrying up to half their capacity
+        while unit.carrying() < capacity / 2.0 {
+            unit.add_item(Item::Flare);
+        }
+        assert_eq!(unit.carrying(), capacity / 2.0);
+        assert_eq!(unit.encumbrance(), 0.0);
+
+        // But walking costs twice as much at full capacity, and no more past it
+        while unit.carrying() < capacity {
+            unit.add_item(Item::Flare);
+        }
+        assert_eq!(unit.carrying(), capacity);
+        assert_eq!(unit.encumbrance(), 1.0);
+        unit.add_item(Item::Scrap);
+        assert_eq!(unit.encumbrance(), 1.0);
+    }
+
+    move_command(
+        &mut map,
+        0,
+        vec![UnitFacing::Bottom],
+        &mut ServerResponses::new(),
+    );
+    assert_eq!(
+        map.units.get(0).unwrap().moves,
+        UnitType::Squaddie.moves() - Unit::WALK_DIAGONAL_COST * 2
+    );
+}
+
+#[test]
+fn critical_hits() {
+    use weapons::WeaponType;
+
+    // Normal hits roll their damage within the weapon's range, while critical hits do half as much again as the best of them
+    for &weapon in &[
+        WeaponType::Rifle,
+        WeaponType::MachineGun,
+        WeaponType::PlasmaRifle,
+    ] {
+        let (low, high) = weapon.damage_range();
+
+        for _ in 0..100 {
+            let damage = weapon.roll_damage();
+            assert!(damage >= low && damage <= high);
+        }
+
+        assert_eq!(weapon.critical_damage(), high * 3 / 2);
+    }
+
+    let mut map = Map::new(30, 30, 1.0);
+    map.units
+        .add(UnitType::Squaddie, Side::PlayerA, 0, 0, UnitFacing::Bottom);
+    let (name, weapon) = {
+        let unit = map.units.get(0).unwrap();
+        (unit.name().to_string(), unit.weapon.tag)
+    };
+    let message = Response::Message(format!(
+        "{} scored a critical hit for {} damage",
+        name,
+        weapon.critical_damage()
+    ));
+
+    // Critical hits should be announced sooner or later
+    let mut announced = false;
+
+    for _ in 0..1000 {
+        {
+            let unit = map.units.get_mut(0).unwrap();
+            unit.moves = unit.tag.moves();
+            unit.weapon.ammo = unit.weapon.tag.capacity();
+        }
+
+        let mut responses = ServerResponses::new();
+        fire_command(&mut map, 0, 0, 2, &mut responses);
+
+        if responses.split().0.contains(&message) {
+            announced = true;
+            break;
+        }
+    }
+
+    assert!(announced);
 }
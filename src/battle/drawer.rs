@@ -10,6 +10,8 @@ use utils::convert_rotation;
 
 const TILE_WIDTH: f32 = 48.0;
 const TILE_HEIGHT: f32 = 24.0;
+// How far up each level of height is drawn
+const LEVEL_HEIGHT: f32 = TILE_HEIGHT / 2.0;
 
 // Convert coordinates from isometric
 pub fn from_map_coords(x: f32, y: f32) -> (f32, f32) {
@@ -122,8 +124,8 @@ impl Camera {
     }
 }
 
-// If a tile is visible, get it's location on the screen
-fn draw_location(ctx: &Context, camera: &Camera, x: f32, y: f32) -> Option<[f32; 2]> {
+// If a tile is visible, get it's location on the screen, raised up by a number of levels
+fn draw_location(ctx: &Context, camera: &Camera, x: f32, y: f32, z: f32) -> Option<[f32; 2]> {
     // Get the maximum x and y values (given that (0, 0) is at the center)
     let (max_x, max_y) = (ctx.width, ctx.height);
     // The x and y difference of a tile compared to another tile on the same row/col
@@ -137,7 +139,7 @@ fn draw_location(ctx: &Context, camera: &Camera, x: f32, y: f32) -> Option<[f32;
 
     // Get the correct position
     let x = (x - camera.x) * x_size + ctx.width / 2.0;
-    let y = -(y - camera.y) * y_size + ctx.height / 2.0;
+    let y = -(y - camera.y) * y_size + ctx.height / 2.0 - z * LEVEL_HEIGHT * camera.zoom;
 
     // Check if the tile is onscreen
     if x > -x_size && y > -y_size * 2.0 && x < max_x + x_size && y < max_y + y_size * 2.0 {
@@ -147,6 +149,20 @@ fn draw_location(ctx: &Context, camera: &Camera, x: f32, y: f32) -> Option<[f32;
     }
 }
 
+// Get the height that a tile is drawn at
+fn level(map: &Map, x: usize, y: usize) -> f32 {
+    f32::from(map.tiles.at(x, y).height)
+}
+
+// Open doors are drawn faded out
+fn wall_opacity(wall: &Wall) -> f32 {
+    if wall.is_open() {
+        0.3
+    } else {
+        1.0
+    }
+}
+
 // Draw all the elements of a particular map tile
 fn draw_tile(x: usize, y: usize, ctx: &mut Context, battle: &Battle) {
     let camera = &battle.camera;
@@ -157,41 +173,86 @@ fn draw_tile(x: usize, y: usize, ctx: &mut Context, battle: &Battle) {
     let tiles = &map.tiles;
     let light = map.light;
 
-    // Get the tile
+    // Get the tile, using how well lit it is if it's visible
     let visibility = tiles.visibility_at(x, y, side);
-    let overlay = visibility.colour(light, debugging);
+    let overlay = if visibility.is_visible() {
+        visibility.colour(tiles.light_at(x, y), debugging)
+    } else {
+        visibility.colour(light, debugging)
+    };
+    // Tint the map for the weather
+    let overlay = map.weather.tint(overlay);
     let tile = tiles.at(x, y);
 
     // If the tile is on the screen, draw it
-    if let Some(dest) = draw_location(ctx, camera, x as f32, y as f32) {
+    if let Some(dest) = draw_location(ctx, camera, x as f32, y as f32, f32::from(tile.height)) {
         ctx.render_with_overlay(tile.base, dest, camera.zoom, overlay);
 
         // Draw the left wall
         if let Some(ref wall) = tile.walls.left {
             let visibility = tiles.left_wall_visibility(x, y, side);
-            ctx.render_with_overlay(
-                wall.tag.left_image(),
+            ctx.render_translucent(
+                wall.image(WallSide::Left),
                 dest,
                 camera.zoom,
                 visibility.colour(light, debugging),
+                wall_opacity(wall),
             );
+
+            // Draw doors, windows and low walls over the material
+            if let Some(image) = wall.tag.overlay(WallSide::Left) {
+                ctx.render_translucent(
+                    image,
+                    dest,
+                    camera.zoom,
+                    visibility.colour(light, debugging),
+                    wall_opacity(wall),
+                );
+            }
         }
 
         // Draw the right wall
         if let Some(ref wall) = tile.walls.top {
             let visibility = tiles.top_wall_visibility(x, y, side);
-            ctx.render_with_overlay(
-                wall.tag.top_image(),
+            ctx.render_translucent(
+                wall.image(WallSide::Top),
                 dest,
                 camera.zoom,
                 visibility.colour(light, debugging),
+                wall_opacity(wall),
             );
+
+            // Draw doors, windows and low walls over the material
+            if let Some(image) = wall.tag.overlay(WallSide::Top) {
+                ctx.render_translucent(
+                    image,
+                    dest,
+                    camera.zoom,
+                    visibility.colour(light, debugging),
+                    wall_opacity(wall),
+                );
+            }
         }
 
         if let Obstacle::Pit(image) = tile.obstacle {
             ctx.render_with_overlay(image, dest, camera.zoom, overlay);
         }
 
+        // Mark the tiles that are part of the objective
+        if map.objective.marks(x, y) {
+            ctx.render_with_overlay(Image::Path, dest, camera.zoom, colours::GREEN);
+        }
+
+        // Mark the spawn zone during deployment
+        if map.deployment.is_active() && Deployment::in_zone(side, y, tiles.height()) {
+            ctx.render_with_overlay(Image::Path, dest, camera.zoom, colours::YELLOW);
+        }
+
+        // Mark the approximate positions of noises
+        if map.noises.iter().any(|noise| noise.x == x && noise.y == y) {
+            ctx.render_with_overlay(Image::NoiseMarker, dest, camera.zoom, colours::BLUE);
+        }
+
         // Draw the cursor if it isn't on an ai unit and or a unit isn't selected
         if !battle.cursor_active() {
             if let Some((cursor_x, cursor_y)) = battle.cursor {
@@ -200,8 +261,8 @@ fn draw_tile(x: usize, y: usize, ctx: &mut Context, battle: &Battle) {
                     // Grey if the tile is not visible
                     let colour = if !visibility.is_visible() {
                         colours::GREY
-                    // Red if the tile has an obstacle
-                    } else if !tile.obstacle.is_empty() {
+                    // Red if the tile has an object on it
+                    } else if tile.obstacle.is_object() {
                         colours::RED
                     // Orange if it has a unit
                     } else if battle.client.map.units.at(x, y).is_some() {
@@ -226,18 +287,45 @@ fn draw_tile(x: usize, y: usize, ctx: &mut Context, battle: &Battle) {
             ctx.render_with_overlay(item.image(), dest, camera.zoom, overlay);
         }
 
+        // Draw any fire or flare on the tile
+        if let Some(image) = tile
+            .light_source
+            .and_then(|source| source.image(battle.time))
+        {
+            ctx.render_with_overlay(image, dest, camera.zoom, overlay);
+        }
+
         // Draw a unit at the position
         if let Some(unit) = battle.client.map.units.at(x, y) {
             // Draw the cursor to show that the unit is selected
             if battle.selected == Some(unit.id) {
                 ctx.render_with_overlay(Image::Cursor, dest, camera.zoom, colours::ORANGE);
+            // Mark downed units
+            } else if unit.is_downed() {
+                ctx.render_with_overlay(Image::DownedMarker, dest, camera.zoom, colours::RED);
             }
 
             unit.render(ctx, dest, camera.zoom, overlay);
+        } else if visibility.is_foggy() {
+            // Draw where an enemy unit was last seen
+            if let Some(ghost) = map.ghosts.at(side, x, y) {
+                ctx.render_translucent(
+                    ghost.tag.image(ghost.facing),
+                    dest,
+                    camera.zoom,
+                    overlay,
+                    ghost.opacity(map.turn()),
+                );
+            }
         }
         // If the tile has an obstacle on it, draw it
-        if let Obstacle::Object(image) = tile.obstacle {
-            ctx.render_with_overlay(image, dest, camera.zoom, overlay);
+        if let Obstacle::Object(ref object) = tile.obstacle {
+            ctx.render_with_overlay(object.image, dest, camera.zoom, overlay);
+        }
+
+        // Draw a smoke cloud over the tile
+        if tile.smoke.is_some() {
+            ctx.render_with_overlay(Image::Smoke, dest, camera.zoom, overlay);
         }
 
         // Draw explosions on the tile
@@ -268,7 +356,7 @@ pub fn draw_map(ctx: &mut Context, battle: &Battle) {
     for x in 0..width {
         let visibility = map.tiles.visibility_at(x, height - 1, side);
 
-        if let Some(dest) = draw_location(ctx, camera, (x + 1) as f32, height as f32) {
+        if let Some(dest) = draw_location(ctx, camera, (x + 1) as f32, height as f32, 0.0) {
             ctx.render_with_overlay(
                 Image::LeftEdge,
                 dest,
@@ -281,7 +369,7 @@ pub fn draw_map(ctx: &mut Context, battle: &Battle) {
     for y in 0..height {
         let visibility = map.tiles.visibility_at(width - 1, y, side);
 
-        if let Some(dest) = draw_location(ctx, camera, width as f32, (y + 1) as f32) {
+        if let Some(dest) = draw_location(ctx, camera, width as f32, (y + 1) as f32, 0.0) {
             ctx.render_with_overlay(
                 Image::RightEdge,
                 dest,
@@ -310,7 +398,13 @@ pub fn draw_battle(ctx: &mut Context, battle: &Battle) {
             for point in points {
                 unit_moves -= i32::from(point.cost);
 
-                if let Some(dest) = draw_location(ctx, camera, point.x as f32, point.y as f32) {
+                if let Some(dest) = draw_location(
+                    ctx,
+                    camera,
+                    point.x as f32,
+                    point.y as f32,
+                    level(map, point.x, point.y),
+                ) {
                     // Render the path tile
 
                     let colour = if unit_moves < 0 {
@@ -333,7 +427,13 @@ pub fn draw_battle(ctx: &mut Context, battle: &Battle) {
                 unit_moves -= i32::from(point.cost);
 
                 if unit_moves >= 0 {
-                    if let Some(dest) = draw_location(ctx, camera, point.x as f32, point.y as f32) {
+                    if let Some(dest) = draw_location(
+                        ctx,
+                        camera,
+                        point.x as f32,
+                        point.y as f32,
+                        level(map, point.x, point.y),
+                    ) {
                         // Render the path cost
                         ctx.render_text(&unit_moves.to_string(), dest[0], dest[1], colours::WHITE);
                     }
@@ -346,7 +446,8 @@ pub fn draw_battle(ctx: &mut Context, battle: &Battle) {
     if battle.cursor_active() {
         if let Some(firing) = battle.selected() {
             if let Some((x, y)) = battle.cursor {
-                if let Some(dest) = draw_location(ctx, camera, x as f32, y as f32) {
+                if let Some(dest) = draw_location(ctx, camera, x as f32, y as f32, level(map, x, y))
+                {
                     // Draw the crosshair
                     ctx.render(Image::CursorCrosshair, dest, camera.zoom);
 
@@ -360,9 +461,25 @@ pub fn draw_battle(ctx: &mut Context, battle: &Battle) {
                         colours::WHITE
                     };
 
-                    // Draw the chance-to-hit
+                    let flank = firing.flank(&map.units, x, y);
+                    let (low, high) = firing.weapon.tag.damage_range();
+
+                    // Show which side a unit under the cursor would be shot in
+                    let flank_info = match map.units.at(x, y) {
+                        Some(_) => format!(" {}", flank),
+                        None => String::new(),
+                    };
+
+                    // Draw the chance-to-hit, the damage range and the chance of a critical hit
                     ctx.render_text(
-                        &format!("{:0.3}%", firing.chance_to_hit(x, y) * 100.0),
+                        &format!(
+                            "{:0.3}% ({}-{} dmg, {:0.0}% crit){}",
+                            firing.chance_to_hit(&map.tiles, x, y, flank) * 100.0,
+                            flank.damage(low),
+                            flank.damage(high),
+                            firing.crit_chance(&map.tiles, x, y, flank) * 100.0,
+                            flank_info
+                        ),
                         dest[0],
                         dest[1] + TILE_HEIGHT * camera.zoom,
                         colour,
@@ -372,13 +489,41 @@ pub fn draw_battle(ctx: &mut Context, battle: &Battle) {
         }
     }
 
+    // Draw the health of the walls under the cursor when debugging
+    if battle.visual_debugging {
+        if let Some((x, y)) = battle.cursor {
+            if let Some(dest) = draw_location(ctx, camera, x as f32, y as f32, level(map, x, y)) {
+                let walls = &map.tiles.at(x, y).walls;
+                let walls = [("Left", &walls.left), ("Top", &walls.top)];
+                let walls = walls
+                    .iter()
+                    .filter_map(|&(name, wall)| wall.as_ref().map(|wall| (name, wall)));
+
+                for (i, (name, wall)) in walls.enumerate() {
+                    ctx.render_text(
+                        &format!(
+                            "{}: {} {}/{}",
+                            name,
+                            wall.material,
+                            wall.health(),
+                            wall.max_health()
+                        ),
+                        dest[0],
+                        dest[1] - (i + 1) as f32 * Context::FONT_HEIGHT,
+                        colours::WHITE,
+                    );
+                }
+            }
+        }
+    }
+
     // Draw all the visible bullets in the response queue
     responses
         .iter()
         .filter_map(Response::as_bullet)
         .for_each(|bullet| {
             // If the bullet is on screen, draw it with the right rotation
-            if let Some(dest) = draw_location(ctx, camera, bullet.x(), bullet.y()) {
+            if let Some(dest) = draw_location(ctx, camera, bullet.x(), bullet.y(), 0.0) {
                 ctx.render_with_rotation(
                     bullet.image(),
                     dest,
@@ -392,7 +537,7 @@ pub fn draw_battle(ctx: &mut Context, battle: &Battle) {
         .iter()
         .filter_map(Response::as_thrown_item)
         .for_each(|thrown_item| {
-            if let Some(dest) = draw_location(ctx, camera, thrown_item.x(), thrown_item.y()) {
+            if let Some(dest) = draw_location(ctx, camera, thrown_item.x(), thrown_item.y(), 0.0) {
                 ctx.render(
                     thrown_item.image(),
                     [
@@ -0,0 +1,224 @@
+// The weapons and items that units start a battle with
+
+use rand;
+use rand::Rng;
+
+use super::units::UnitType;
+use items::Item;
+use weapons::WeaponType;
+
+use std::fmt;
+use std::mem::replace;
+
+// The kinds of item that can be picked for a loadout
+#[derive(Copy, Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum Kit {
+    Clip,
+    Bandages,
+    Grenade,
+    SmokeGrenade,
+    IncendiaryGrenade,
+    Flare,
+    Turret,
+}
+
+impl Kit {
+    pub const ALL: [Kit; 7] = [
+        Kit::Clip,
+        Kit::Bandages,
+        Kit::Grenade,
+        Kit::SmokeGrenade,
+        Kit::IncendiaryGrenade,
+        Kit::Flare,
+        Kit::Turret,
+    ];
+
+    // Get the item, using the right clip for a weapon
+    fn item(self, weapon: WeaponType) -> Item {
+        match self {
+            Kit::Clip => match weapon {
+                WeaponType::Rifle => Item::RifleClip(weapon.capacity()),
+                WeaponType::MachineGun => Item::MachineGunClip(weapon.capacity()),
+                WeaponType::PlasmaRifle => Item::PlasmaClip(weapon.capacity()),
+            },
+            Kit::Bandages => Item::Bandages,
+            Kit::Grenade => Item::Grenade(None),
+            Kit::SmokeGrenade => Item::SmokeGrenade,
+            Kit::IncendiaryGrenade => Item::IncendiaryGrenade,
+            Kit::Flare => Item::Flare,
+            Kit::Turret => Item::Turret,
+        }
+    }
+
+    // How many points the item costs
+    fn points(self) -> u16 {
+        match self {
+            Kit::Clip | Kit::Bandages | Kit::SmokeGrenade => 2,
+            Kit::Grenade => 4,
+            Kit::IncendiaryGrenade => 3,
+            Kit::Flare => 1,
+            Kit::Turret => 12,
+        }
+    }
+}
+
+impl fmt::Display for Kit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                Kit::Clip => "Clips",
+                Kit::Bandages => "Bandages",
+                Kit::Grenade => "Grenades",
+                Kit::SmokeGrenade => "Smoke Grenades",
+                Kit::IncendiaryGrenade => "Incendiary Grenades",
+                Kit::Flare => "Flares",
+                Kit::Turret => "Turrets",
+            }
+        )
+    }
+}
+
+// How many points a weapon costs
+fn weapon_points(weapon: WeaponType) -> u16 {
+    match weapon {
+        WeaponType::Rifle => 10,
+        WeaponType::MachineGun => 15,
+        WeaponType::PlasmaRifle => 20,
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct Loadout {
+    pub weapon: WeaponType,
+    kit: Vec<Kit>,
+}
+
+impl Loadout {
+    // Create the standard loadout for a unit type, picking a random weapon
+    pub fn new(tag: UnitType) -> Self {
+        let weapons = tag.weapons();
+        let weapon = weapons[rand::thread_rng().gen_range(0, weapons.len())];
+
+        let kit = match tag {
+            UnitType::Squaddie => vec![
+                Kit::Clip,
+                Kit::Clip,
+                Kit::Bandages,
+                Kit::Grenade,
+                Kit::SmokeGrenade,
+                Kit::Flare,
+            ],
+            _ => Vec::new(),
+        };
+
+        Self { weapon, kit }
+    }
+
+    // Get how many of a kind of item are in the loadout
+    pub fn count(&self, kit: Kit) -> usize {
+        self.kit.iter().filter(|other| **other == kit).count()
+    }
+
+    pub fn items(&self) -> Vec<Item> {
+        self.kit.iter().map(|kit| kit.item(self.weapon)).collect()
+    }
+
+    pub fn weight(&self) -> f32 {
+        self.items()
+            .iter()
+            .fold(self.weapon.weight(), |total, item| total + item.weight())
+    }
+
+    pub fn points(&self) -> u16 {
+        self.kit
+            .iter()
+            .fold(weapon_points(self.weapon), |total, kit| {
+                total + kit.points()
+            })
+    }
+
+    // Can a unit type carry the loadout, with it costing no more than the budget (if any)
+    pub fn fits(&self, tag: UnitType, budget: Option<u16>) -> bool {
+        self.weight() <= tag.capacity()
+            && budget.map(|budget| self.points() <= budget).unwrap_or(true)
+    }
+
+    // Add an item if it fits
+    pub fn add(&mut self, kit: Kit, tag: UnitType, budget: Option<u16>) {
+        self.kit.push(kit);
+
+        if !self.fits(tag, budget) {
+            self.kit.pop();
+        }
+    }
+
+    pub fn remove(&mut self, kit: Kit) {
+        if let Some(index) = self.kit.iter().rposition(|other| *other == kit) {
+            self.kit.remove(index);
+        }
+    }
+
+    // Switch to the next weapon the unit type can use, if the loadout still fits
+    pub fn change_weapon(&mut self, tag: UnitType, budget: Option<u16>) {
+        let weapons = tag.weapons();
+        let index = weapons
+            .iter()
+            .position(|weapon| *weapon == self.weapon)
+            .map(|index| (index + 1) % weapons.len())
+            .unwrap_or(0);
+
+        let weapon = replace(&mut self.weapon, weapons[index]);
+
+        if !self.fits(tag, budget) {
+            self.weapon = weapon;
+        }
+    }
+
+    // Make sure the loadout suits a unit type, dropping items until it fits
+    pub fn fit(&mut self, tag: UnitType, budget: Option<u16>) {
+        if !tag.weapons().contains(&self.weapon) {
+            *self = Self::new(tag);
+        }
+
+        while !self.fits(tag, budget) && self.kit.pop().is_some() {}
+    }
+}
+
+#[test]
+fn loadout_limits() {
+    let mut loadout = Loadout::new(UnitType::Squaddie);
+    assert_eq!(loadout.count(Kit::Clip), 2);
+    assert!(loadout.fits(UnitType::Squaddie, None));
+
+    // Items shouldn't be added past the budget
+    let budget = Some(loadout.points());
+    loadout.add(Kit::Flare, UnitType::Squaddie, budget);
+    assert_eq!(loadout.count(Kit::Flare), 1);
+    loadout.remove(Kit::Grenade);
+    loadout.add(Kit::Flare, UnitType::Squaddie, budget);
+    assert_eq!(loadout.count(Kit::Flare), 2);
+
+    // Or past what the unit can carry
+    for _ in 0..100 {
+        loadout.add(Kit::Bandages, UnitType::Squaddie, None);
+    }
+    assert!(loadout.weight() <= UnitType::Squaddie.capacity());
+
+    // And lowering the budget should drop items
+    loadout.fit(UnitType::Squaddie, Some(20));
+    assert!(loadout.points() <= 20);
+
+    // Weapons shouldn't be switched to if they go over the budget
+    loadout.weapon = WeaponType::Rifle;
+    let budget = Some(loadout.points());
+    loadout.change_weapon(UnitType::Squaddie, budget);
+    assert_eq!(loadout.weapon, WeaponType::Rifle);
+    loadout.change_weapon(UnitType::Squaddie, None);
+    assert_eq!(loadout.weapon, WeaponType::MachineGun);
+
+    // Switching unit types should give a suitable weapon
+    loadout.fit(UnitType::Machine, None);
+    assert_eq!(loadout.weapon, WeaponType::PlasmaRifle);
+}
@@ -0,0 +1,95 @@
+// The phase before the first turn, where each side places its units in its spawn zone
+
+use super::super::units::*;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct Deployment {
+    active: bool,
+    confirmed: Vec<Side>,
+}
+
+impl Deployment {
+    // How many rows deep each side's spawn zone is
+    const ZONE_DEPTH: usize = 3;
+
+    pub fn new() -> Self {
+        Self {
+            active: true,
+            confirmed: Vec::new(),
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn has_confirmed(&self, side: Side) -> bool {
+        self.confirmed.contains(&side)
+    }
+
+    // Work out if a row is in a side's spawn zone
+    pub fn in_zone(side: Side, y: usize, height: usize) -> bool {
+        match side {
+            Side::PlayerA => y < Self::ZONE_DEPTH,
+            Side::PlayerB => y + Self::ZONE_DEPTH >= height,
+        }
+    }
+
+    // Confirm a side's placements, ending the deployment once both sides have
+    pub fn confirm(&mut self, side: Side) {
+        if !self.has_confirmed(side) {
+            self.confirmed.push(side);
+        }
+
+        self.active = !(self.has_confirmed(Side::PlayerA) && self.has_confirmed(Side::PlayerB));
+    }
+}
+
+#[test]
+fn deployment_phase() {
+    use super::super::messages::*;
+    use super::Map;
+    use settings::Settings;
+
+    let settings = Settings::default();
+    let mut map = Map::new(10, 10, 1.0);
+    map.deployment = Deployment::new();
+    map.units
+        .add(UnitType::Squaddie, Side::PlayerA, 0, 0, UnitFacing::Bottom);
+    map.units
+        .add(UnitType::Squaddie, Side::PlayerB, 9, 9, UnitFacing::Top);
+
+    let deploy = |unit, x, y| ClientMessage::Deploy {
+        unit,
+        x,
+        y,
+        facing: UnitFacing::Left,
+    };
+
+    // Units can only be placed in their own side's spawn zone
+    map.handle_message(deploy(0, 5, 2), &settings, Side::PlayerA);
+    assert_eq!(map.units.get(0).unwrap().x, 5);
+    assert_eq!(map.units.get(0).unwrap().facing, UnitFacing::Left);
+
+    // And see from where they've been placed
+    assert!(map.tiles.visibility_at(3, 2, Side::PlayerA).is_visible());
+    assert!(!map.tiles.visibility_at(0, 1, Side::PlayerA).is_visible());
+    map.handle_message(deploy(0, 5, 5), &settings, Side::PlayerA);
+    assert_eq!(map.units.get(0).unwrap().y, 2);
+    map.handle_message(deploy(1, 5, 1), &settings, Side::PlayerA);
+    assert_eq!(map.units.get(1).unwrap().y, 9);
+
+    // Both sides can deploy at the same time, but can't do anything else
+    map.handle_message(deploy(1, 4, 7), &settings, Side::PlayerB);
+    assert_eq!(map.units.get(1).unwrap().y, 7);
+    map.handle_message(ClientMessage::EndTurn, &settings, Side::PlayerA);
+    assert_eq!(map.side, Side::PlayerA);
+
+    // And the battle only starts once both sides confirm
+    map.handle_message(ClientMessage::ConfirmDeployment, &settings, Side::PlayerA);
+    map.handle_message(deploy(0, 6, 2), &settings, Side::PlayerA);
+    assert_eq!(map.units.get(0).unwrap().x, 5);
+    assert!(map.deployment.is_active());
+    map.handle_message(ClientMessage::ConfirmDeployment, &settings, Side::PlayerB);
+    assert!(!map.deployment.is_active());
+}
@@ -0,0 +1,135 @@
+// The last known positions of enemy units that each side has lost sight of
+
+use super::super::units::*;
+use super::tiles::*;
+
+// Where an enemy unit was last seen
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Ghost {
+    pub id: u8,
+    pub tag: UnitType,
+    pub x: usize,
+    pub y: usize,
+    pub facing: UnitFacing,
+    pub turn: u16,
+    // The side that remembers the unit
+    side: Side,
+}
+
+impl Ghost {
+    const OPACITY: f32 = 0.6;
+    const MIN_OPACITY: f32 = 0.2;
+    const FADE_RATE: f32 = 0.1;
+
+    fn new(unit: &Unit, side: Side, turn: u16) -> Self {
+        Self {
+            side,
+            id: unit.id,
+            tag: unit.tag,
+            x: unit.x,
+            y: unit.y,
+            facing: unit.facing,
+            turn,
+        }
+    }
+
+    // Ghosts fade the longer it's been since the unit was seen
+    pub fn opacity(&self, turn: u16) -> f32 {
+        let age = f32::from(turn.saturating_sub(self.turn));
+        (Self::OPACITY - age * Self::FADE_RATE).max(Self::MIN_OPACITY)
+    }
+}
+
+// The memory of both sides
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct Ghosts {
+    ghosts: Vec<Ghost>,
+}
+
+impl Ghosts {
+    // Iterate over the ghosts remembered by a side
+    pub fn get(&self, side: Side) -> impl Iterator<Item = &Ghost> {
+        self.ghosts.iter().filter(move |ghost| ghost.side == side)
+    }
+
+    // Get the ghost at a position, if any
+    pub fn at(&self, side: Side, x: usize, y: usize) -> Option<&Ghost> {
+        self.get(side).find(|ghost| ghost.x == x && ghost.y == y)
+    }
+
+    // Update what a side remembers from what it can currently see
+    pub fn update(&mut self, side: Side, units: &Units, tiles: &Tiles, turn: u16) {
+        // Forget units that are visible again and positions that have been checked, whether or not
+        // the unit is still alive
+        self.ghosts.retain(|ghost| {
+            if ghost.side != side {
+                return true;
+            }
+
+            let forget = tiles.visibility_at(ghost.x, ghost.y, side).is_visible()
+                || units
+                    .get(ghost.id)
+                    .map(|unit| tiles.visibility_at(unit.x, unit.y, side).is_visible())
+                    .unwrap_or(false);
+
+            !forget && !units.on_side(ghost.x, ghost.y, side)
+        });
+
+        // Remember the positions of the visible enemies
+        self.ghosts.extend(
+            tiles
+                .visible_units(units, side)
+                .filter(|unit| unit.side != side)
+                .map(|unit| Ghost::new(unit, side, turn)),
+        );
+    }
+
+    // Clone only the memory of one side
+    pub fn clone_visible(&self, side: Side) -> Self {
+        Self {
+            ghosts: self.get(side).cloned().collect(),
+        }
+    }
+}
+
+#[test]
+fn ghost_memory() {
+    let mut units = Units::new();
+    units.add(UnitType::Squaddie, Side::PlayerA, 0, 0, UnitFacing::Bottom);
+    units.add(UnitType::Squaddie, Side::PlayerB, 0, 2, UnitFacing::Top);
+
+    let mut tiles = Tiles::new(30, 30);
+    tiles.update_visibility(&units);
+
+    let mut ghosts = Ghosts::default();
+    ghosts.update(Side::PlayerA, &units, &tiles, 1);
+    assert!(ghosts.at(Side::PlayerA, 0, 2).is_some());
+
+    // Move the enemy out of sight and it should still be remembered where it was last seen
+
+    units.get_mut(1).unwrap().y = 29;
+    units.get_mut(0).unwrap().facing = UnitFacing::Top;
+    tiles.update_visibility(&units);
+    ghosts.update(Side::PlayerA, &units, &tiles, 2);
+
+    let ghost = ghosts.at(Side::PlayerA, 0, 2).unwrap();
+    assert_eq!((ghost.id, ghost.turn), (1, 1));
+    assert_eq!(ghosts.get(Side::PlayerA).count(), 1);
+
+    // Until the position is checked again, even though the enemy is still alive
+
+    units.get_mut(0).unwrap().facing = UnitFacing::Bottom;
+    tiles.update_visibility(&units);
+    ghosts.update(Side::PlayerA, &units, &tiles, 3);
+    assert!(ghosts.at(Side::PlayerA, 0, 2).is_none());
+
+    // Only the memory of one side is sent to it
+
+    assert_eq!(
+        ghosts
+            .clone_visible(Side::PlayerB)
+            .get(Side::PlayerA)
+            .count(),
+        0
+    );
+}
@@ -5,23 +5,33 @@ use bincode;
 
 use error::*;
 use settings::*;
+use utils::*;
 
 use std::fs::*;
 use std::path::*;
 
+mod deployment;
+mod ghosts;
 mod grid;
 mod iter_2d;
+mod reinforcements;
 mod tiles;
 mod vision;
 mod walls;
+mod weather;
 
 use super::commands::*;
 use super::messages::*;
+use super::objectives::*;
 use super::responses::*;
 use super::units::*;
 
+pub use self::deployment::*;
+pub use self::ghosts::*;
+pub use self::reinforcements::*;
 pub use self::tiles::*;
 pub use self::walls::*;
+pub use self::weather::*;
 
 const EXTENSION: &str = ".sav";
 
@@ -31,20 +41,39 @@ pub struct Map {
     pub units: Units,
     pub tiles: Tiles,
     pub light: f32,
+    pub weather: Weather,
     pub side: Side,
+    pub objective: Objective,
+    pub scores: Scores,
+    pub turn_limit: Option<u16>,
+    pub ghosts: Ghosts,
+    pub noises: Vec<Noise>,
+    pub reinforcements: Reinforcements,
+    pub deployment: Deployment,
     turn: u16,
 }
 
 impl Map {
     // Create a new map
     pub fn new(width: usize, height: usize, light: f32) -> Map {
-        Map {
+        let mut map = Map {
             light,
+            weather: Weather::Clear,
             units: Units::new(),
             tiles: Tiles::new(width, height),
             turn: 1,
             side: Side::PlayerA,
-        }
+            objective: Objective::Annihilation,
+            scores: Scores::default(),
+            turn_limit: None,
+            ghosts: Ghosts::default(),
+            noises: Vec::new(),
+            reinforcements: Reinforcements::default(),
+            deployment: Deployment::default(),
+        };
+
+        map.update_light();
+        map
     }
 
     pub fn new_or_load(settings: &SkirmishSettings) -> Result<Self> {
@@ -61,37 +90,62 @@ impl Map {
             f32::from(settings.light) / 10.0,
         );
 
+        map.turn_limit = settings.turn_limit;
+        map.set_weather(settings.weather);
+        map.reinforcements = Reinforcements::new(settings.reinforcement_waves());
+        map.deployment = Deployment::new();
+
         // Add player units
-        for x in 0..settings.player_a_units {
-            map.units.add(
+        for (x, loadout) in settings.player_a_loadouts.iter().enumerate() {
+            map.units.add_with_loadout(
                 settings.player_a_unit_type,
                 Side::PlayerA,
                 x,
                 0,
                 UnitFacing::Bottom,
+                loadout,
             );
         }
 
         // Add ai units
-        for y in settings.width - settings.player_b_units..settings.width {
-            map.units.add(
+        let offset = settings.width - settings.player_b_loadouts.len();
+
+        for (i, loadout) in settings.player_b_loadouts.iter().enumerate() {
+            map.units.add_with_loadout(
                 settings.player_b_unit_type,
                 Side::PlayerB,
-                y,
+                offset + i,
                 settings.height - 1,
                 UnitFacing::Top,
+                loadout,
             );
         }
 
         // Generate tiles
         map.tiles.generate(&map.units);
+        map.update_light();
+
+        // Set up the objective
+        let objective = Objective::new(settings.objective, &mut map);
+        map.objective = objective;
 
         map
     }
 
+    // Update the light levels of the tiles
+    pub fn update_light(&mut self) {
+        self.tiles.update_light(self.light);
+    }
+
+    // Change the weather, which the tiles need to know about for sight and chance-to-hit
+    pub fn set_weather(&mut self, weather: Weather) {
+        self.weather = weather;
+        self.tiles.set_weather(weather);
+    }
+
     // Work out if a tile is taken or not
     pub fn taken(&self, x: usize, y: usize) -> bool {
-        !self.tiles.at(x, y).obstacle.is_empty() || self.units.at(x, y).is_some()
+        self.tiles.at(x, y).obstacle.is_object() || self.units.at(x, y).is_some()
     }
 
     // Work out how many units of a particular side are visible to the other side
@@ -161,13 +215,86 @@ impl Map {
         side: Side,
     ) -> (Vec<Response>, Vec<Response>) {
         match message {
-            ClientMessage::EndTurn => self.end_turn(side),
             ClientMessage::SaveGame(filename) => self.save(filename, settings),
+            ClientMessage::Deploy { unit, x, y, facing } => self.deploy(unit, x, y, facing, side),
+            ClientMessage::ConfirmDeployment => self.confirm_deployment(side),
+            // Nothing else can happen until both sides have deployed
+            _ if self.deployment.is_active() => {
+                let mut responses = ServerResponses::new();
+                responses.push(side, Response::InvalidCommand);
+                responses
+            }
+            ClientMessage::EndTurn => self.end_turn(side),
             ClientMessage::Command { unit, command } => self.perform_command(unit, command, side),
         }
         .split()
     }
 
+    // Place a unit somewhere in its side's spawn zone during deployment
+    fn deploy(
+        &mut self,
+        id: u8,
+        x: usize,
+        y: usize,
+        facing: UnitFacing,
+        side: Side,
+    ) -> ServerResponses {
+        let mut responses = ServerResponses::new();
+
+        let valid = self.deployment.is_active()
+            && !self.deployment.has_confirmed(side)
+            && x < self.tiles.width()
+            && y < self.tiles.height()
+            && Deployment::in_zone(side, y, self.tiles.height())
+            && !self.tiles.at(x, y).obstacle.is_object()
+            && !self.tiles.at(x, y).obstacle.is_pit()
+            && self
+                .units
+                .at(x, y)
+                .map(|unit| unit.id == id)
+                .unwrap_or(true)
+            && self
+                .units
+                .get(id)
+                .map(|unit| unit.side == side)
+                .unwrap_or(false);
+
+        if valid {
+            let unit = self.units.get_mut(id).unwrap();
+            unit.x = x;
+            unit.y = y;
+            unit.facing = facing;
+
+            self.tiles.update_visibility(&self.units);
+            responses.push_and_update_state(self);
+        } else {
+            responses.push(side, Response::InvalidCommand);
+        }
+
+        responses
+    }
+
+    // Confirm a side's placements, starting the battle if the other side is ready too
+    fn confirm_deployment(&mut self, side: Side) -> ServerResponses {
+        let mut responses = ServerResponses::new();
+
+        if !self.deployment.is_active() || self.deployment.has_confirmed(side) {
+            responses.push(side, Response::InvalidCommand);
+            return responses;
+        }
+
+        self.deployment.confirm(side);
+        responses.push_message(format!("{} is ready", side));
+
+        if !self.deployment.is_active() {
+            responses.push_message("The battle begins".into());
+        }
+
+        self.tiles.update_visibility(&self.units);
+        responses.push_and_update_state(self);
+        responses
+    }
+
     pub fn perform_command(&mut self, id: u8, command: Command, side: Side) -> ServerResponses {
         let mut responses = ServerResponses::new();
 
@@ -175,11 +302,12 @@ impl Map {
         // A) The side is correct
         // B) The unit exists
         // C) The unit is on that side
+        // D) The unit isn't down
         if !(side == self.side
             && self
                 .units
                 .get(id)
-                .map(|unit| unit.side == side)
+                .map(|unit| unit.side == side && !unit.is_downed())
                 .unwrap_or(false))
         {
             return responses;
@@ -191,47 +319,112 @@ impl Map {
             Command::Walk(path) => move_command(self, id, path, &mut responses),
             Command::Turn(facing) => turn_command(self, id, facing, &mut responses),
             Command::UseItem(item) => use_item_command(self, id, item, &mut responses),
+            Command::UseItemOn { item, target } => {
+                use_item_on_command(self, id, item, target, &mut responses)
+            }
+            Command::GiveItem { item, target } => {
+                give_item_command(self, id, item, target, &mut responses)
+            }
+            Command::SalvageItem(item) => salvage_item_command(self, id, item, &mut responses),
             Command::PickupItem(item) => pickup_item_command(self, id, item, &mut responses),
             Command::DropItem(item) => drop_item_command(self, id, item, &mut responses),
             Command::ThrowItem { item, x, y } => {
                 throw_item_command(self, id, item, x, y, &mut responses)
             }
             Command::Fire { x, y } => fire_command(self, id, x, y, &mut responses),
+            Command::ToggleDoor { x, y } => toggle_door_command(self, id, x, y, &mut responses),
+            Command::DeployItem { item, x, y } => {
+                deploy_item_command(self, id, item, x, y, &mut responses)
+            }
+            Command::UseAbility { ability, x, y } => {
+                use_ability_command(self, id, ability, x, y, &mut responses)
+            }
+            Command::Drag { target, x, y } => drag_command(self, id, target, x, y, &mut responses),
         }
 
         // All commands should have a cost, so if one doesn't, it failed
-        if self.units.get(id).unwrap().moves == moves {
+        // (a unit that no longer exists has done something)
+        if self
+            .units
+            .get(id)
+            .map(|unit| unit.moves == moves)
+            .unwrap_or(false)
+        {
             responses.push(side, Response::InvalidCommand);
         }
 
-        let player_a_units = self.units.count(Side::PlayerA);
-        let player_b_units = self.units.count(Side::PlayerB);
+        self.push_game_over(&mut responses);
 
-        // Push the gameover response
-        if player_a_units == 0 || player_b_units == 0 {
-            let player_a_units_lost = self.units.max_player_a_units - player_a_units;
-            let player_b_units_lost = self.units.max_player_b_units - player_b_units;
+        responses
+    }
 
-            responses.push(
-                Side::PlayerA,
-                Response::GameOver(GameStats {
-                    won: player_a_units != 0,
-                    units_lost: player_a_units_lost,
-                    units_killed: player_b_units_lost,
-                }),
-            );
+    // Push the game over responses if either side has won
+    fn push_game_over(&self, responses: &mut ServerResponses) {
+        // Downed units count as alive, but can't stop their side from being wiped out
+        let player_a_units = self.units.count(Side::PlayerA);
+        let player_b_units = self.units.count(Side::PlayerB);
+        let player_a_standing = self.units.count_standing(Side::PlayerA);
+        let player_b_standing = self.units.count_standing(Side::PlayerB);
+        let objective_status = self.objective.status(&self.units);
+        let player_a_score = self.scores.get(Side::PlayerA);
+        let player_b_score = self.scores.get(Side::PlayerB);
+        let turn_limit_reached = self.turn_limit_reached();
+
+        // Completing or failing the objective takes priority over either side being wiped out,
+        // and if the turn limit is reached the side with the most points wins
+        let winner = match objective_status {
+            ObjectiveStatus::Completed => Some(Side::PlayerA),
+            ObjectiveStatus::Failed => Some(Side::PlayerB),
+            ObjectiveStatus::InProgress if player_a_standing == 0 => Some(Side::PlayerB),
+            ObjectiveStatus::InProgress if player_b_standing == 0 => Some(Side::PlayerA),
+            ObjectiveStatus::InProgress if turn_limit_reached => {
+                let player_a_points = player_a_score.points();
+                let player_b_points = player_b_score.points();
+
+                if player_a_points > player_b_points {
+                    Some(Side::PlayerA)
+                } else if player_b_points > player_a_points {
+                    Some(Side::PlayerB)
+                } else {
+                    None
+                }
+            }
+            ObjectiveStatus::InProgress => return,
+        };
 
-            responses.push(
-                Side::PlayerB,
-                Response::GameOver(GameStats {
-                    won: player_b_units != 0,
-                    units_lost: player_b_units_lost,
-                    units_killed: player_a_units_lost,
-                }),
-            );
-        }
+        // Extracted units aren't counted as lost
+        let player_a_units_lost =
+            self.units.max_player_a_units - player_a_units - self.objective.extracted();
+        let player_b_units_lost = self.units.max_player_b_units - player_b_units;
+        let objective = self.objective.tag();
+
+        responses.push(
+            Side::PlayerA,
+            Response::GameOver(GameStats {
+                won: winner == Some(Side::PlayerA),
+                units_lost: player_a_units_lost,
+                units_killed: player_b_units_lost,
+                objective,
+                objective_status,
+                turn_limit_reached,
+                score: player_a_score,
+                enemy_score: player_b_score,
+            }),
+        );
 
-        responses
+        responses.push(
+            Side::PlayerB,
+            Response::GameOver(GameStats {
+                won: winner == Some(Side::PlayerB),
+                units_lost: player_b_units_lost,
+                units_killed: player_a_units_lost,
+                objective,
+                objective_status,
+                turn_limit_reached,
+                score: player_b_score,
+                enemy_score: player_a_score,
+            }),
+        );
     }
 
     pub fn end_turn(&mut self, side: Side) -> ServerResponses {
@@ -241,30 +434,90 @@ impl Map {
             return responses;
         }
 
+        self.objective
+            .end_turn(side, &mut self.units, &mut self.tiles, &mut responses);
+
         for unit in self.units.iter_mut() {
-            unit.moves = unit.tag.moves();
+            unit.refill_moves();
+
+            if unit.side == side {
+                unit.cool_down();
+            }
         }
 
+        bleed_out(self, side, &mut responses);
+
+        // Scans last until the scanning side's next turn
+        self.tiles.clear_scans(side.enemies());
+
+        burn_fires(self, &mut responses);
+        self.tiles.burn_down_lights();
+        self.tiles.thin_smoke();
+        self.update_light();
+
+        detonate_grenades(self, &mut responses);
+
         match self.side {
             Side::PlayerA => self.side = Side::PlayerB,
             Side::PlayerB => {
+                // Award a point to whoever is holding the objective at the end of the round
+                if let Some(holder) = self.objective.held_by(&self.units) {
+                    self.scores.get_mut(holder).objective += 1;
+                }
+
                 self.turn += 1;
                 self.side = Side::PlayerA;
             }
         }
 
+        self.reinforce(&mut responses);
+
         responses.push_and_update_state(self);
+        self.push_game_over(&mut responses);
         responses
     }
 
+    // Bring in the reinforcements due for the side whose turn it is
+    fn reinforce(&mut self, responses: &mut ServerResponses) {
+        for wave in self.reinforcements.due(self.side, self.turn) {
+            let positions = wave.positions(&self.tiles, |x, y| {
+                !self.taken(x, y) && !self.tiles.at(x, y).obstacle.is_pit()
+            });
+
+            for &(x, y, facing) in &positions {
+                self.units.add(wave.tag, wave.side, x, y, facing);
+            }
+
+            if !positions.is_empty() {
+                responses.push_message(format!(
+                    "{} {} reinforcements have arrived for {}",
+                    positions.len(),
+                    wave.tag,
+                    wave.side
+                ));
+            }
+        }
+    }
+
     pub fn clone_visible(&mut self, side: Side) -> Self {
         // Update visibility first
         self.tiles.update_visibility(&self.units);
+        // Then update the side's memory of where the enemy units are
+        self.ghosts
+            .update(side, &self.units, &self.tiles, self.turn);
 
         Self {
             light: self.light,
+            weather: self.weather,
             turn: self.turn,
             side: self.side,
+            objective: self.objective.clone(),
+            scores: self.scores.clone(),
+            turn_limit: self.turn_limit,
+            ghosts: self.ghosts.clone_visible(side),
+            noises: Vec::new(),
+            reinforcements: self.reinforcements.clone_visible(side),
+            deployment: self.deployment.clone(),
             units: self
                 .tiles
                 .visible_units(&self.units, side)
@@ -277,12 +530,43 @@ impl Map {
     pub fn update_from(&mut self, mut new: Map, side: Side) {
         self.tiles.update_from(new.tiles, side);
         new.tiles = self.tiles.clone();
+        // Noises are only known to the client, so keep the recent ones
+        new.noises = self
+            .noises
+            .drain(..)
+            .filter(|noise| noise.is_recent(new.turn))
+            .collect();
         *self = new;
     }
 
+    // Remember a noise, returning false if it's close to a similar noise that was already heard
+    pub fn hear(&mut self, noise: Noise) -> bool {
+        let range = (Noise::ACCURACY * 3) as f32;
+
+        match self.noises.iter_mut().find(|heard| {
+            heard.tag == noise.tag && distance_under(heard.x, heard.y, noise.x, noise.y, range)
+        }) {
+            Some(heard) => {
+                *heard = noise;
+                false
+            }
+            None => {
+                self.noises.push(noise);
+                true
+            }
+        }
+    }
+
     pub fn turn(&self) -> u16 {
         self.turn
     }
+
+    // Has the game gone past the maximum number of turns
+    pub fn turn_limit_reached(&self) -> bool {
+        self.turn_limit
+            .map(|limit| self.turn > limit)
+            .unwrap_or(false)
+    }
 }
 
 #[test]
@@ -312,3 +596,32 @@ fn load_save() {
     );
     Map::load(&output).unwrap();
 }
+
+#[test]
+fn turn_limit() {
+    let mut map = Map::new(10, 10, 1.0);
+    map.units
+        .add(UnitType::Squaddie, Side::PlayerA, 0, 0, UnitFacing::Bottom);
+    map.units
+        .add(UnitType::Squaddie, Side::PlayerB, 9, 9, UnitFacing::Top);
+    map.turn_limit = Some(1);
+
+    map.scores.get_mut(Side::PlayerA).record_damage(25, false);
+
+    // The game shouldn't be over until the round has finished
+
+    let (player_a_responses, _) = map.end_turn(Side::PlayerA).split();
+    assert!(player_a_responses
+        .iter()
+        .all(|response| response.as_game_over().is_none()));
+
+    // Then player A should win on points
+
+    let (player_a_responses, _) = map.end_turn(Side::PlayerB).split();
+    let stats = player_a_responses
+        .iter()
+        .filter_map(Response::as_game_over)
+        .next()
+        .unwrap();
+    assert!(stats.won && stats.turn_limit_reached);
+}
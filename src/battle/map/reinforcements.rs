@@ -0,0 +1,192 @@
+// Waves of units that join a side on a chosen turn
+
+use rand;
+use rand::Rng;
+
+use super::super::units::*;
+use super::tiles::*;
+
+use std::cmp::{max, min};
+use std::fmt;
+
+// Where a wave of reinforcements arrives
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Arrival {
+    // The edge of the map that the side started on
+    SpawnZone,
+    // The left or right edge of the map
+    MapEdge,
+}
+
+impl Arrival {
+    pub fn toggle(&mut self) {
+        *self = match *self {
+            Arrival::SpawnZone => Arrival::MapEdge,
+            Arrival::MapEdge => Arrival::SpawnZone,
+        }
+    }
+}
+
+impl fmt::Display for Arrival {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                Arrival::SpawnZone => "Spawn Zone",
+                Arrival::MapEdge => "Map Edge",
+            }
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Wave {
+    pub side: Side,
+    pub tag: UnitType,
+    pub count: usize,
+    pub turn: u16,
+    pub arrival: Arrival,
+}
+
+impl Wave {
+    // Work out where the units in the wave should go and which way they should face,
+    // filling the tiles closest to the edge (and the middle of it) first
+    pub fn positions<F: Fn(usize, usize) -> bool>(
+        &self,
+        tiles: &Tiles,
+        free: F,
+    ) -> Vec<(usize, usize, UnitFacing)> {
+        let width = tiles.width();
+        let height = tiles.height();
+
+        // Units face away from the edge they arrive at
+        let facing = match (self.arrival, self.side) {
+            (Arrival::SpawnZone, Side::PlayerA) => UnitFacing::Bottom,
+            (Arrival::SpawnZone, Side::PlayerB) => UnitFacing::Top,
+            (Arrival::MapEdge, _) => {
+                if rand::thread_rng().gen() {
+                    UnitFacing::Right
+                } else {
+                    UnitFacing::Left
+                }
+            }
+        };
+
+        // Get the distance from the edge and from the middle of the edge
+        let key = |x: usize, y: usize| match facing {
+            UnitFacing::Bottom => (y, middle_distance(x, width)),
+            UnitFacing::Top => (height - 1 - y, middle_distance(x, width)),
+            UnitFacing::Right => (x, middle_distance(y, height)),
+            _ => (width - 1 - x, middle_distance(y, height)),
+        };
+
+        let mut positions: Vec<_> = tiles.iter().filter(|&(x, y)| free(x, y)).collect();
+        positions.sort_by_key(|&(x, y)| key(x, y));
+
+        positions
+            .into_iter()
+            .take(self.count)
+            .map(|(x, y)| (x, y, facing))
+            .collect()
+    }
+}
+
+// How far a position along an edge is from its middle
+fn middle_distance(position: usize, length: usize) -> usize {
+    let middle = length / 2;
+    max(position, middle) - min(position, middle)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct Reinforcements {
+    waves: Vec<Wave>,
+}
+
+impl Reinforcements {
+    pub fn new(waves: Vec<Wave>) -> Self {
+        Self { waves }
+    }
+
+    // Take the waves that are due to arrive for a side
+    pub fn due(&mut self, side: Side, turn: u16) -> Vec<Wave> {
+        let (due, waiting) = self
+            .waves
+            .drain(..)
+            .partition(|wave| wave.side == side && wave.turn <= turn);
+
+        self.waves = waiting;
+        due
+    }
+
+    // Describe the waves still to come, with each on a new line
+    pub fn info(&self) -> String {
+        self.waves
+            .iter()
+            .map(|wave| {
+                format!(
+                    "\nReinforcements: {} {} on turn {}",
+                    wave.count, wave.tag, wave.turn
+                )
+            })
+            .collect()
+    }
+
+    // Only let a side know about its own reinforcements
+    pub fn clone_visible(&self, side: Side) -> Self {
+        Self {
+            waves: self
+                .waves
+                .iter()
+                .filter(|wave| wave.side == side)
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+#[test]
+fn reinforcement_waves() {
+    use super::Map;
+
+    let mut map = Map::new(10, 10, 1.0);
+    map.units
+        .add(UnitType::Squaddie, Side::PlayerA, 5, 0, UnitFacing::Bottom);
+    map.units
+        .add(UnitType::Machine, Side::PlayerB, 5, 9, UnitFacing::Top);
+    map.reinforcements = Reinforcements::new(vec![
+        Wave {
+            side: Side::PlayerA,
+            tag: UnitType::Squaddie,
+            count: 2,
+            turn: 2,
+            arrival: Arrival::SpawnZone,
+        },
+        Wave {
+            side: Side::PlayerB,
+            tag: UnitType::Machine,
+            count: 3,
+            turn: 2,
+            arrival: Arrival::MapEdge,
+        },
+    ]);
+
+    // Nothing should arrive before the wave's turn
+    map.end_turn(Side::PlayerA);
+    assert_eq!(map.units.count(Side::PlayerB), 1);
+
+    // Player A's reinforcements arrive at the start of their turn, next to their spawn
+    map.end_turn(Side::PlayerB);
+    assert_eq!(map.units.count(Side::PlayerA), 3);
+    assert!(map.units.at(4, 0).is_some() && map.units.at(6, 0).is_some());
+    assert_eq!(map.units.count(Side::PlayerB), 1);
+
+    // And player B's arrive along the left or right edge
+    map.end_turn(Side::PlayerA);
+    assert_eq!(map.units.count(Side::PlayerB), 4);
+    assert!(map
+        .units
+        .iter()
+        .filter(|unit| unit.tag == UnitType::Machine && unit.y != 9)
+        .all(|unit| unit.x == 0 || unit.x == 9));
+}
@@ -6,6 +6,7 @@ use rand::Rng;
 use super::super::units::*;
 use super::iter_2d::Iter2D;
 use super::walls::*;
+use super::weather::Weather;
 
 use super::grid::*;
 use items::Item;
@@ -15,6 +16,20 @@ use utils::*;
 // todo: make map generation better!
 const MIN_PIT_SIZE: usize = 2;
 const MAX_PIT_SIZE: usize = 5;
+const MIN_ROOF_SIZE: usize = 3;
+const MAX_ROOF_SIZE: usize = 6;
+// How many levels up rooftops are
+const ROOF_HEIGHT: u8 = 2;
+// How much further units can see for each level they are above what they're looking at
+const HIGH_GROUND_SIGHT: f32 = 1.5;
+// How far units can see in complete darkness compared to daylight
+const DARK_SIGHT: f32 = 0.4;
+// How many turns a smoke cloud lasts for
+pub const SMOKE_TURNS: u8 = 3;
+// How far around the target tile a scan reveals
+const SCAN_RADIUS: f32 = 4.0;
+// How many frames of the fire animation are shown each second
+const FIRE_FRAMES_PER_SECOND: f32 = 6.0;
 
 // The visibility of the tile
 #[derive(Copy, Clone, Serialize, Deserialize, Debug, is_enum_variant, PartialEq)]
@@ -81,9 +96,78 @@ fn combine_visibilities(a: Visibility, b: Visibility) -> Visibility {
     }
 }
 
+// Something that lights up the tiles around it
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, is_enum_variant)]
+pub enum LightSource {
+    Lamp,
+    Fire(u8),
+    Flare(u8),
+}
+
+impl LightSource {
+    pub const FIRE_TURNS: u8 = 2;
+    pub const FLARE_TURNS: u8 = 3;
+
+    // The radius of tiles that get lit up
+    pub fn radius(self) -> f32 {
+        match self {
+            LightSource::Lamp => 3.0,
+            LightSource::Fire(_) => 2.0,
+            LightSource::Flare(_) => 5.0,
+        }
+    }
+
+    // The image to draw on the tile at a point in time, if any
+    pub fn image(self, time: f32) -> Option<Image> {
+        match self {
+            LightSource::Lamp => None,
+            LightSource::Fire(_) => Some(match (time * FIRE_FRAMES_PER_SECOND) as usize % 3 {
+                0 => Image::Fire1,
+                1 => Image::Fire2,
+                _ => Image::Fire3,
+            }),
+            LightSource::Flare(_) => Some(Image::LitFlare),
+        }
+    }
+
+    // Burn down the light source at the end of a turn, returning none if it has burnt out
+    fn burn_down(self) -> Option<Self> {
+        match self {
+            LightSource::Lamp => Some(self),
+            LightSource::Fire(turns) if turns > 1 => Some(LightSource::Fire(turns - 1)),
+            LightSource::Flare(turns) if turns > 1 => Some(LightSource::Flare(turns - 1)),
+            _ => None,
+        }
+    }
+}
+
+// An object on a tile that blocks movement and can be destroyed
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Object {
+    pub image: Image,
+    health: i16,
+}
+
+impl Object {
+    pub fn new(image: Image) -> Self {
+        Self {
+            health: match image {
+                Image::ObjectRebar => 75,
+                _ => 40,
+            },
+            image,
+        }
+    }
+
+    pub fn damage(&mut self, damage: i16) -> bool {
+        self.health -= damage;
+        self.health <= 0
+    }
+}
+
 #[derive(Serialize, Deserialize, is_enum_variant, Debug, Clone, PartialEq)]
 pub enum Obstacle {
-    Object(Image),
+    Object(Object),
     Pit(Image),
     Empty,
 }
@@ -96,6 +180,9 @@ pub struct Tile {
     pub decoration: Option<Image>,
     pub walls: Walls,
     pub items: Vec<Item>,
+    pub light_source: Option<LightSource>,
+    pub smoke: Option<u8>,
+    pub height: u8,
 }
 
 impl Tile {
@@ -107,6 +194,9 @@ impl Tile {
             decoration: None,
             walls: Walls::new(),
             items: Vec::new(),
+            light_source: None,
+            smoke: None,
+            height: 0,
         }
     }
 
@@ -114,6 +204,66 @@ impl Tile {
     fn set_pit(&mut self, pit_image: Image) {
         self.obstacle = Obstacle::Pit(pit_image);
         self.decoration = None;
+        self.height = 0;
+    }
+
+    // Get the level of the tile, with pits being below the ground
+    pub fn level(&self) -> i16 {
+        if self.obstacle.is_pit() {
+            -1
+        } else {
+            i16::from(self.height)
+        }
+    }
+
+    // Damage the object on the tile, leaving rubble if it is destroyed
+    pub fn damage_object(&mut self, damage: i16) -> bool {
+        let destroyed = match self.obstacle {
+            Obstacle::Object(ref mut object) => object.damage(damage),
+            _ => false,
+        };
+
+        if destroyed {
+            self.obstacle = Obstacle::Empty;
+            self.decoration = Some(Image::Rubble);
+
+            // Destroying a lamp puts it out
+            if self.light_source == Some(LightSource::Lamp) {
+                self.light_source = None;
+            }
+        }
+
+        destroyed
+    }
+
+    pub fn is_burning(&self) -> bool {
+        self.light_source
+            .map(|source| source.is_fire())
+            .unwrap_or(false)
+    }
+
+    // Could a fire spread onto the tile
+    pub fn flammable(&self) -> bool {
+        !self.is_burning()
+            && self.light_source.is_none()
+            && (self.decoration == Some(Image::Rubble)
+                || self.items.iter().any(|item| item.flammable()))
+    }
+
+    // Set the tile alight, unless it has another light source
+    pub fn ignite(&mut self) {
+        if self.light_source.is_none() || self.is_burning() {
+            self.light_source = Some(LightSource::Fire(LightSource::FIRE_TURNS));
+        }
+    }
+
+    // Burn up the flammable items and decoration on the tile, leaving scorch marks
+    pub fn burn(&mut self) {
+        self.items.retain(|item| !item.flammable());
+
+        if self.decoration == Some(Image::Rubble) {
+            self.decoration = Some(Image::Scorch);
+        }
     }
 
     // Actions that occur when the tile is walked on
@@ -133,11 +283,22 @@ impl Tile {
     }
 }
 
+// An area revealed to a side by a scan
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct Scan {
+    side: Side,
+    x: usize,
+    y: usize,
+}
+
 // A 2D array of tiles
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Tiles {
     tiles: Grid<Tile>,
     visibility_grids: [Grid<Visibility>; 2],
+    light: Grid<f32>,
+    scans: Vec<Scan>,
+    weather: Weather,
 }
 
 impl Tiles {
@@ -158,9 +319,132 @@ impl Tiles {
                 Grid::new(width, height, || Visibility::Invisible),
                 Grid::new(width, height, || Visibility::Invisible),
             ],
+            light: Grid::new(width, height, || 1.0),
+            scans: Vec::new(),
+            weather: Weather::Clear,
+        }
+    }
+
+    pub fn set_weather(&mut self, weather: Weather) {
+        self.weather = weather;
+    }
+
+    pub fn weather(&self) -> Weather {
+        self.weather
+    }
+
+    // Get how well lit a tile is, from 0.0 to 1.0
+    pub fn light_at(&self, x: usize, y: usize) -> f32 {
+        *self.light.at(x, y)
+    }
+
+    // Work out how well lit each tile is from the ambient light and the light sources
+    pub fn update_light(&mut self, ambient: f32) {
+        let sources: Vec<_> = self
+            .iter()
+            .filter_map(|(x, y)| self.at(x, y).light_source.map(|source| (x, y, source)))
+            .collect();
+
+        for (x, y) in self.iter() {
+            let lit = sources.iter().any(|&(source_x, source_y, source)| {
+                distance_under(x, y, source_x, source_y, source.radius())
+            });
+
+            *self.light.at_mut(x, y) = if lit { 1.0 } else { ambient };
+        }
+    }
+
+    // Burn down the fires and flares at the end of a turn
+    pub fn burn_down_lights(&mut self) {
+        for (x, y) in self.iter() {
+            let tile = self.at_mut(x, y);
+            tile.light_source = tile.light_source.and_then(LightSource::burn_down);
+        }
+    }
+
+    // Set the tiles in a radius on fire
+    pub fn ignite(&mut self, x: usize, y: usize, radius: f32) {
+        for (tile_x, tile_y) in self.iter() {
+            if distance_under(x, y, tile_x, tile_y, radius) {
+                self.at_mut(tile_x, tile_y).ignite();
+            }
+        }
+    }
+
+    // Get the flammable tiles next to the burning ones, that a fire will spread to
+    pub fn spreading_fire(&self) -> Vec<(usize, usize)> {
+        let fires: Vec<_> = self
+            .iter()
+            .filter(|&(x, y)| self.at(x, y).is_burning())
+            .collect();
+
+        self.iter()
+            .filter(|&(x, y)| {
+                self.at(x, y).flammable()
+                    && fires.iter().any(|&(fire_x, fire_y)| {
+                        distance_under(x, y, fire_x, fire_y, 1.5)
+                            && !self.wall_between(fire_x, fire_y, x, y, Passage::Fire)
+                    })
+            })
+            .collect()
+    }
+
+    // Fill the tiles in a radius with smoke
+    pub fn add_smoke(&mut self, x: usize, y: usize, radius: f32) {
+        for (tile_x, tile_y) in self.iter() {
+            if distance_under(x, y, tile_x, tile_y, radius) {
+                self.at_mut(tile_x, tile_y).smoke = Some(SMOKE_TURNS);
+            }
+        }
+    }
+
+    // Thin out the smoke clouds at the end of a turn
+    pub fn thin_smoke(&mut self) {
+        for (x, y) in self.iter() {
+            let smoke = &mut self.at_mut(x, y).smoke;
+            *smoke = smoke.and_then(|turns| if turns > 1 { Some(turns - 1) } else { None });
         }
     }
 
+    // How far a unit could see to a tile, depending on how well lit the tile is
+    pub fn sight(&self, unit: &Unit, x: usize, y: usize) -> f32 {
+        let light = if unit.tag.night_vision() {
+            1.0
+        } else {
+            self.light_at(x, y)
+        };
+
+        // Units can see further from high ground
+        let levels_above = (self.at(unit.x, unit.y).level() - self.at(x, y).level()).max(0);
+        let sight = unit.tag.sight() + f32::from(levels_above) * HIGH_GROUND_SIGHT;
+
+        sight * lerp(DARK_SIGHT, 1.0, light) * self.weather.sight_modifier()
+    }
+
+    // Get the extra cost of moving between two adjacent tiles, or none if the step is too high
+    pub fn climb_cost(&self, a_x: usize, a_y: usize, b_x: usize, b_y: usize) -> Option<u16> {
+        match self.at(b_x, b_y).level() - self.at(a_x, a_y).level() {
+            0 => Some(0),
+            1 => Some(Unit::CLIMB_COST),
+            height if height < 0 => Some(Unit::DESCEND_COST),
+            _ => None,
+        }
+    }
+
+    // Get the damage a unit takes moving between two adjacent tiles from falling into a pit or
+    // off a ledge
+    pub fn fall_damage(&self, a_x: usize, a_y: usize, b_x: usize, b_y: usize) -> i16 {
+        let (a, b) = (self.at(a_x, a_y), self.at(b_x, b_y));
+
+        let levels = if b.obstacle.is_pit() && !a.obstacle.is_pit() {
+            a.level() - b.level()
+        } else {
+            a.level() - b.level() - 1
+        };
+
+        levels.max(0) * Unit::FALL_DAMAGE
+    }
+
     pub fn visibility_at(&self, x: usize, y: usize, side: Side) -> Visibility {
         match side {
             Side::PlayerA => *self.visibility_grids[0].at(x, y),
@@ -207,11 +491,15 @@ impl Tiles {
 
             // Add in objects
             if !unit && rng.gen::<f32>() < 0.05 {
-                tile.obstacle = Obstacle::Object(if rng.gen() {
+                tile.obstacle = Obstacle::Object(Object::new(if rng.gen() {
                     Image::ObjectRebar
                 } else {
                     Image::ObjectRubble
-                });
+                }));
+            // Add in the occasional lamp
+            } else if !unit && rng.gen::<f32>() < 0.01 {
+                tile.obstacle = Obstacle::Object(Object::new(Image::ObjectLamp));
+                tile.light_source = Some(LightSource::Lamp);
             }
         }
 
@@ -221,16 +509,33 @@ impl Tiles {
             rng.gen_range(MIN_PIT_SIZE, MAX_PIT_SIZE + 1),
         );
 
+        // Generate a rooftop
+        self.add_roof(
+            rng.gen_range(MIN_ROOF_SIZE, MAX_ROOF_SIZE + 1),
+            rng.gen_range(MIN_ROOF_SIZE, MAX_ROOF_SIZE + 1),
+        );
+
         // Add in the walls
         for (x, y) in self.iter() {
             if rng.gen::<f32>() < 0.1 {
-                if rng.gen::<bool>() {
-                    self.add_left_wall(x, y, WallType::Ruin1);
-                    self.add_top_wall(x, y + 1, WallType::Ruin1);
+                let (tag, left_x) = if rng.gen::<bool>() {
+                    (WallType::Ruin1, x)
                 } else {
-                    self.add_left_wall(x + 1, y, WallType::Ruin2);
-                    self.add_top_wall(x, y + 1, WallType::Ruin2);
-                }
+                    (WallType::Ruin2, x + 1)
+                };
+
+                // Sometimes make the top of the ruin a door, window or low wall
+                let top_tag = match rng.gen_range(0, 8) {
+                    0 => WallType::Door,
+                    1 => WallType::Window,
+                    2 => WallType::LowWall,
+                    _ => tag.clone(),
+                };
+
+                let material = WallMaterial::ALL[rng.gen_range(0, WallMaterial::ALL.len())];
+
+                self.add_left_wall(left_x, y, tag, material);
+                self.add_top_wall(x, y + 1, top_tag, material);
             }
         }
 
@@ -239,16 +544,16 @@ impl Tiles {
     }
 
     // Add a left wall if possible
-    pub fn add_left_wall(&mut self, x: usize, y: usize, tag: WallType) {
+    pub fn add_left_wall(&mut self, x: usize, y: usize, tag: WallType, material: WallMaterial) {
         if self.tiles.in_bounds(x, y) && (self.not_pit(x, y) || self.not_pit(x - 1, y)) {
-            self.at_mut(x, y).walls.set_left(tag);
+            self.at_mut(x, y).walls.set_left(tag, material);
         }
     }
 
     // Add a top wall if possible
-    pub fn add_top_wall(&mut self, x: usize, y: usize, tag: WallType) {
+    pub fn add_top_wall(&mut self, x: usize, y: usize, tag: WallType, material: WallMaterial) {
         if self.tiles.in_bounds(x, y) && (self.not_pit(x, y) || self.not_pit(x, y - 1)) {
-            self.at_mut(x, y).walls.set_top(tag);
+            self.at_mut(x, y).walls.set_top(tag, material);
         }
     }
 
@@ -257,6 +562,41 @@ impl Tiles {
         self.tiles.in_bounds(x, y) && !self.at(x, y).obstacle.is_pit()
     }
 
+    // Blast a pit into the ground at a position, if there is nothing in the way
+    pub fn blast_pit(&mut self, x: usize, y: usize, units: &Units) -> bool {
+        let tile = self.at_mut(x, y);
+
+        if units.at(x, y).is_some() || !tile.items.is_empty() || !tile.obstacle.is_empty() {
+            return false;
+        }
+
+        tile.set_pit(Image::PitCenter);
+        tile.light_source = None;
+        tile.smoke = None;
+        true
+    }
+
+    // Raise a rectangle of tiles up to a rooftop with stairs leading up to it
+    fn add_roof(&mut self, width: usize, height: usize) {
+        let mut rng = rand::thread_rng();
+
+        let roof_x = rng.gen_range(1, self.width().saturating_sub(width).max(2));
+        let roof_y = rng.gen_range(1, self.height().saturating_sub(height).max(2));
+
+        for (x, y) in Iter2D::new(width, height) {
+            if self.not_pit(roof_x + x, roof_y + y) {
+                self.at_mut(roof_x + x, roof_y + y).height = ROOF_HEIGHT;
+            }
+        }
+
+        // Add the stairs on the left side of the roof
+        let stairs_y = roof_y + rng.gen_range(0, height);
+
+        if self.not_pit(roof_x - 1, stairs_y) {
+            self.at_mut(roof_x - 1, stairs_y).height = ROOF_HEIGHT - 1;
+        }
+    }
+
     fn add_pit(&mut self, width: usize, height: usize) {
         // Generate pit position and size
         let mut rng = rand::thread_rng();
@@ -349,37 +689,67 @@ impl Tiles {
         units
             .iter()
             .filter(|unit| unit.side == side)
-            .map(|unit| self.line_of_sight(unit.x, unit.y, x, y, unit.tag.sight(), unit.facing))
+            .map(|unit| {
+                self.line_of_sight(unit.x, unit.y, x, y, self.sight(unit, x, y), unit.facing)
+            })
             // Get the minimum distance or none
             .fold(None, |sum, dist| {
                 sum.and_then(|sum| dist.map(|dist| min(sum, dist)))
                     .or(sum)
                     .or(dist)
             })
+            // Tiles near a scan are visible too
+            .or_else(|| {
+                self.scans
+                    .iter()
+                    .filter(|scan| scan.side == side)
+                    .find(|scan| distance_under(scan.x, scan.y, x, y, SCAN_RADIUS))
+                    .map(|scan| distance(scan.x, scan.y, x, y) as u8)
+            })
     }
 
-    // Is the wall space between two horizontal tiles empty
-    pub fn horizontal_clear(&self, x: usize, y: usize) -> bool {
-        self.at(x, y).walls.left.is_none()
+    // Reveal the area around a tile to a side
+    pub fn add_scan(&mut self, side: Side, x: usize, y: usize) {
+        self.scans.push(Scan { side, x, y });
     }
 
-    // Is the wall space between two vertical tiles empty
-    pub fn vertical_clear(&self, x: usize, y: usize) -> bool {
-        self.at(x, y).walls.top.is_none()
+    // Remove the scans of a side
+    pub fn clear_scans(&mut self, side: Side) {
+        self.scans.retain(|scan| scan.side != side);
+    }
+
+    // Can something get through the wall space between two horizontal tiles
+    pub fn horizontal_clear(&self, x: usize, y: usize, passage: Passage) -> bool {
+        self.at(x, y)
+            .walls
+            .left
+            .as_ref()
+            .map(|wall| wall.allows(passage))
+            .unwrap_or(true)
+    }
+
+    // Can something get through the wall space between two vertical tiles
+    pub fn vertical_clear(&self, x: usize, y: usize, passage: Passage) -> bool {
+        self.at(x, y)
+            .walls
+            .top
+            .as_ref()
+            .map(|wall| wall.allows(passage))
+            .unwrap_or(true)
     }
 
     // Is a diagonal clear
-    pub fn diagonal_clear(&self, x: usize, y: usize, tl_to_br: bool) -> bool {
+    pub fn diagonal_clear(&self, x: usize, y: usize, tl_to_br: bool, passage: Passage) -> bool {
         if x.wrapping_sub(1) >= self.width() - 1 || y.wrapping_sub(1) >= self.height() - 1 {
             return false;
         }
 
         // Check the walls between the tiles
 
-        let top = self.horizontal_clear(x, y - 1);
-        let left = self.vertical_clear(x - 1, y);
-        let right = self.vertical_clear(x, y);
-        let bottom = self.horizontal_clear(x, y);
+        let top = self.horizontal_clear(x, y - 1, passage);
+        let left = self.vertical_clear(x - 1, y, passage);
+        let right = self.vertical_clear(x, y, passage);
+        let bottom = self.horizontal_clear(x, y, passage);
 
         // Check that there isn't a wall across the tiles and the right corners are open
 
@@ -392,6 +762,29 @@ impl Tiles {
             }
     }
 
+    // Get the position of the door between two laterally adjacent tiles, if any
+    pub fn door_between(
+        &self,
+        a_x: usize,
+        a_y: usize,
+        b_x: usize,
+        b_y: usize,
+    ) -> Option<(usize, usize, WallSide)> {
+        let (x, y, side) = match (b_x as isize - a_x as isize, b_y as isize - a_y as isize) {
+            (1, 0) => (b_x, b_y, WallSide::Left),
+            (-1, 0) => (a_x, a_y, WallSide::Left),
+            (0, 1) => (b_x, b_y, WallSide::Top),
+            (0, -1) => (a_x, a_y, WallSide::Top),
+            _ => return None,
+        };
+
+        self.at(x, y)
+            .walls
+            .get(side)
+            .filter(|wall| wall.tag == WallType::Door)
+            .map(|_| (x, y, side))
+    }
+
     // What should the visiblity of a left wall at a position be
     pub fn left_wall_visibility(&self, x: usize, y: usize, side: Side) -> Visibility {
         let visibility = self.visibility_at(x, y, side);
@@ -465,6 +858,14 @@ impl Tiles {
         Self {
             tiles,
             visibility_grids: grids,
+            light: self.light.clone(),
+            scans: self
+                .scans
+                .iter()
+                .filter(|scan| scan.side == side)
+                .cloned()
+                .collect(),
+            weather: self.weather,
         }
     }
 
@@ -502,6 +903,34 @@ fn walk_on_tile() {
     assert_eq!(tile.decoration, Some(Image::SkeletonCracked));
 }
 
+#[test]
+fn light_sources() {
+    let mut units = Units::new();
+    units.add(UnitType::Squaddie, Side::PlayerA, 0, 0, UnitFacing::Bottom);
+    units.add(UnitType::Machine, Side::PlayerB, 29, 0, UnitFacing::Bottom);
+
+    let mut tiles = Tiles::new(30, 30);
+    tiles.update_light(0.0);
+    tiles.update_visibility(&units);
+
+    // In the dark, squaddies can't see as far as machines
+
+    assert!(tiles.visibility_at(0, 5, Side::PlayerA).is_invisible());
+    assert!(tiles.visibility_at(29, 5, Side::PlayerB).is_visible());
+
+    // But a flare lights up the area
+
+    tiles.at_mut(0, 5).light_source = Some(LightSource::Flare(1));
+    tiles.update_light(0.0);
+    tiles.update_visibility(&units);
+    assert!(tiles.visibility_at(0, 5, Side::PlayerA).is_visible());
+
+    // Until it burns out
+
+    tiles.burn_down_lights();
+    assert_eq!(tiles.at(0, 5).light_source, None);
+}
+
 #[test]
 fn map_generation() {
     let units = Units::new();
@@ -512,3 +941,26 @@ fn map_generation() {
     Tiles::new(10, 30).generate(&units);
     Tiles::new(30, 10).generate(&units);
 }
+
+#[test]
+fn elevation() {
+    let mut tiles = Tiles::new(30, 30);
+    let mut units = Units::new();
+    units.add(UnitType::Squaddie, Side::PlayerA, 0, 0, UnitFacing::Bottom);
+
+    tiles.at_mut(0, 0).height = ROOF_HEIGHT;
+    tiles.at_mut(1, 0).height = ROOF_HEIGHT - 1;
+
+    // Stairs can be climbed up and down but roofs can't be climbed straight onto
+    assert_eq!(tiles.climb_cost(1, 0, 0, 0), Some(Unit::CLIMB_COST));
+    assert_eq!(tiles.climb_cost(0, 0, 1, 0), Some(Unit::DESCEND_COST));
+    assert_eq!(tiles.climb_cost(0, 1, 0, 0), None);
+
+    // Jumping off the roof hurts
+    assert_eq!(tiles.fall_damage(1, 0, 2, 0), 0);
+    assert_eq!(tiles.fall_damage(0, 0, 0, 1), Unit::FALL_DAMAGE);
+
+    // And units can see further from up there
+    let unit = units.get(0).unwrap();
+    assert!(tiles.sight(unit, 0, 5) > unit.tag.sight());
+}
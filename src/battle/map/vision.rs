@@ -7,9 +7,26 @@ use super::walls::*;
 use line_drawing::*;
 use rand;
 use std::mem::*;
+use utils::{chance_to_hit, distance};
+
+// How much smoke reduces the chance to hit
+const SMOKE_HIT_MODIFIER: f32 = 0.5;
+// How much being behind a low wall reduces the chance to hit
+const COVER_HIT_MODIFIER: f32 = 0.6;
+// How much firing from high ground increases the chance to hit
+const HIGH_GROUND_HIT_MODIFIER: f32 = 1.25;
+// The chance of any hit being critical
+const BASE_CRIT_CHANCE: f32 = 0.05;
+// How much more likely a critical hit is against a target that isn't in cover, scaled by the flank
+const NO_COVER_CRIT_BONUS: f32 = 0.2;
+// How much more likely a critical hit is at point-blank range, dropping off to nothing at `CRIT_RANGE`
+const CLOSE_CRIT_BONUS: f32 = 0.15;
+const CRIT_RANGE: f32 = 5.0;
 
 // A point for line-of-sight
 type Point = (isize, isize);
+// The tiles a bullet passes through and the wall that stopped it, if any
+type FirePath = (Vec<(usize, usize)>, Option<(Point, WallSide)>);
 
 // Sort two points on the y axis
 fn sort(a: Point, b: Point) -> (Point, Point, bool) {
@@ -30,17 +47,30 @@ fn from_point(point: Point) -> (usize, usize) {
     (point.0 as usize, point.1 as usize)
 }
 
-// Return whether there is a wall between two tiles
 impl Tiles {
-    fn wall_between(&self, a: Point, b: Point) -> bool {
+    // Return whether there is a wall between two adjacent tiles
+    pub fn wall_between(
+        &self,
+        a_x: usize,
+        a_y: usize,
+        b_x: usize,
+        b_y: usize,
+        passage: Passage,
+    ) -> bool {
+        let (a, b, _) = sort(to_point(a_x, a_y), to_point(b_x, b_y));
+        self.wall_between_points(a, b, passage)
+    }
+
+    // Return whether there is a wall between two tiles, sorted on the y axis
+    fn wall_between_points(&self, a: Point, b: Point, passage: Passage) -> bool {
         let ((a_x, a_y), (b_x, b_y)) = (from_point(a), from_point(b));
 
         !match (b.0 - a.0, b.1 - a.1) {
-            (0, 1) => self.vertical_clear(b_x, b_y),
-            (1, 0) => self.horizontal_clear(b_x, b_y),
-            (-1, 0) => self.horizontal_clear(a_x, a_y),
-            (-1, 1) => self.diagonal_clear(a_x, b_y, false),
-            (1, 1) => self.diagonal_clear(b_x, b_y, true),
+            (0, 1) => self.vertical_clear(b_x, b_y, passage),
+            (1, 0) => self.horizontal_clear(b_x, b_y, passage),
+            (-1, 0) => self.horizontal_clear(a_x, a_y, passage),
+            (-1, 1) => self.diagonal_clear(a_x, b_y, false, passage),
+            (1, 1) => self.diagonal_clear(b_x, b_y, true, passage),
             _ => unreachable!(),
         }
     }
@@ -61,55 +91,9 @@ impl Tiles {
         let mut iter = Bresenham::new(start, end)
             .steps()
             // Filter to steps with walls between
-            .filter(|&(a, b)| self.wall_between(a, b))
+            .filter(|&(a, b)| self.wall_between_points(a, b, Passage::Fire))
             // Map to the containing tile and wall direction
-            .map(|(a, b)| match (b.0 - a.0, b.1 - a.1) {
-                (0, 1) => (b, WallSide::Top),
-                (1, 0) => (b, WallSide::Left),
-                (-1, 0) => (a, WallSide::Left),
-                // For diagonal steps we have to randomly pick one of the two closest walls
-                (-1, 1) | (1, 1) => {
-                    let left_to_right = a.0 < b.0;
-
-                    // Get the four walls segments between the tiles if left-to-right or their flipped equivalents
-                    let (mut top, mut left, mut right, mut bottom) = if left_to_right {
-                        ((b.0, a.1), (a.0, b.1), b, b)
-                    } else {
-                        (a, (a.0, b.1), b, (a.0, b.1))
-                    };
-
-                    // Swap the points around if the line is reversed
-                    if reversed {
-                        swap(&mut top, &mut bottom);
-                        swap(&mut left, &mut right);
-                    }
-
-                    // Get whether each of these segments contain walls
-                    let top_block = !self.horizontal_clear(top.0 as usize, top.1 as usize);
-                    let left_block = !self.vertical_clear(left.0 as usize, left.1 as usize);
-                    let right_block = !self.vertical_clear(right.0 as usize, right.1 as usize);
-                    let bottom_block = !self.horizontal_clear(bottom.0 as usize, bottom.1 as usize);
-
-                    // Get the pairs of walls to choose from
-                    let (wall_a, wall_b) = if top_block && left_block {
-                        ((top, WallSide::Left), (left, WallSide::Top))
-                    } else if left_block && right_block {
-                        ((left, WallSide::Top), (right, WallSide::Top))
-                    } else if top_block && bottom_block {
-                        ((top, WallSide::Left), (bottom, WallSide::Left))
-                    } else {
-                        ((bottom, WallSide::Left), (right, WallSide::Top))
-                    };
-
-                    // Choose a random wall
-                    if rand::random::<bool>() {
-                        wall_a
-                    } else {
-                        wall_b
-                    }
-                }
-                _ => unreachable!(),
-            });
+            .map(|(a, b)| self.wall_crossed(a, b, reversed));
 
         // Return either the last or first wall found or none
         if reversed {
@@ -119,6 +103,164 @@ impl Tiles {
         }
     }
 
+    // Get the wall (and the tile containing it) that a step between two tiles crosses
+    fn wall_crossed(&self, a: Point, b: Point, reversed: bool) -> (Point, WallSide) {
+        match (b.0 - a.0, b.1 - a.1) {
+            (0, 1) => (b, WallSide::Top),
+            (1, 0) => (b, WallSide::Left),
+            (-1, 0) => (a, WallSide::Left),
+            // For diagonal steps we have to randomly pick one of the two closest walls
+            (-1, 1) | (1, 1) => {
+                let left_to_right = a.0 < b.0;
+
+                // Get the four walls segments between the tiles if left-to-right or their flipped equivalents
+                let (mut top, mut left, mut right, mut bottom) = if left_to_right {
+                    ((b.0, a.1), (a.0, b.1), b, b)
+                } else {
+                    (a, (a.0, b.1), b, (a.0, b.1))
+                };
+
+                // Swap the points around if the line is reversed
+                if reversed {
+                    swap(&mut top, &mut bottom);
+                    swap(&mut left, &mut right);
+                }
+
+                // Get whether each of these segments contain walls
+                let top_block =
+                    !self.horizontal_clear(top.0 as usize, top.1 as usize, Passage::Fire);
+                let left_block =
+                    !self.vertical_clear(left.0 as usize, left.1 as usize, Passage::Fire);
+                let right_block =
+                    !self.vertical_clear(right.0 as usize, right.1 as usize, Passage::Fire);
+                let bottom_block =
+                    !self.horizontal_clear(bottom.0 as usize, bottom.1 as usize, Passage::Fire);
+
+                // Get the pairs of walls to choose from
+                let (wall_a, wall_b) = if top_block && left_block {
+                    ((top, WallSide::Left), (left, WallSide::Top))
+                } else if left_block && right_block {
+                    ((left, WallSide::Top), (right, WallSide::Top))
+                } else if top_block && bottom_block {
+                    ((top, WallSide::Left), (bottom, WallSide::Left))
+                } else {
+                    ((bottom, WallSide::Left), (right, WallSide::Top))
+                };
+
+                // Choose a random wall
+                if rand::random::<bool>() {
+                    wall_a
+                } else {
+                    wall_b
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    // Get the tiles a bullet passes through on its way to a point (which can be off the map),
+    // in order and stopping at the edge of the map or at a wall, along with the wall that stopped it
+    pub fn line_of_fire_tiles(&self, start_x: usize, start_y: usize, end: Point) -> FirePath {
+        let in_bounds = |point: Point| {
+            point.0 >= 0
+                && point.1 >= 0
+                && (point.0 as usize) < self.width()
+                && (point.1 as usize) < self.height()
+        };
+
+        let (start, end, reversed) = sort(to_point(start_x, start_y), end);
+        let mut steps: Vec<_> = Bresenham::new(start, end).steps().collect();
+
+        if reversed {
+            steps.reverse();
+        }
+
+        let mut tiles = Vec::new();
+
+        for (a, b) in steps {
+            let next = if reversed { a } else { b };
+
+            if !in_bounds(next) {
+                break;
+            } else if self.wall_between_points(a, b, Passage::Fire) {
+                return (tiles, Some(self.wall_crossed(a, b, reversed)));
+            }
+
+            tiles.push(from_point(next));
+        }
+
+        (tiles, None)
+    }
+
+    fn smoke_at(&self, point: Point) -> bool {
+        let (x, y) = from_point(point);
+        self.at(x, y).smoke.is_some()
+    }
+
+    // Is there any smoke between two tiles (not counting the starting tile)
+    pub fn smoke_between(&self, a_x: usize, a_y: usize, b_x: usize, b_y: usize) -> bool {
+        Bresenham::new(to_point(a_x, a_y), to_point(b_x, b_y))
+            .skip(1)
+            .any(|point| self.smoke_at(point))
+    }
+
+    fn gives_cover(&self, x: usize, y: usize, side: WallSide) -> bool {
+        self.at(x, y)
+            .walls
+            .get(side)
+            .map(Wall::gives_cover)
+            .unwrap_or(false)
+    }
+
+    // Is a tile behind a low wall from the direction of a shot from another tile
+    pub fn in_cover(&self, a_x: usize, a_y: usize, b_x: usize, b_y: usize) -> bool {
+        match Bresenham::new(to_point(a_x, a_y), to_point(b_x, b_y))
+            .steps()
+            .last()
+        {
+            Some((from, to)) => {
+                let (x, y) = from_point(to);
+
+                (from.0 < to.0 && self.gives_cover(x, y, WallSide::Left))
+                    || (from.0 > to.0 && self.gives_cover(x + 1, y, WallSide::Left))
+                    || (from.1 < to.1 && self.gives_cover(x, y, WallSide::Top))
+                    || (from.1 > to.1 && self.gives_cover(x, y + 1, WallSide::Top))
+            }
+            None => false,
+        }
+    }
+
+    // Get the chance to hit a tile from another, taking smoke, cover, height and the weather into account
+    pub fn chance_to_hit(&self, a_x: usize, a_y: usize, b_x: usize, b_y: usize) -> f32 {
+        let mut chance = chance_to_hit(a_x, a_y, b_x, b_y) * self.weather().hit_modifier();
+
+        if self.smoke_between(a_x, a_y, b_x, b_y) {
+            chance *= SMOKE_HIT_MODIFIER;
+        }
+
+        if self.in_cover(a_x, a_y, b_x, b_y) {
+            chance *= COVER_HIT_MODIFIER;
+        }
+
+        if self.at(a_x, a_y).level() > self.at(b_x, b_y).level() {
+            chance *= HIGH_GROUND_HIT_MODIFIER;
+        }
+
+        chance.min(1.0)
+    }
+
+    // Get the chance of a hit from one tile on another being critical, with a flank on the target
+    pub fn crit_chance(&self, a_x: usize, a_y: usize, b_x: usize, b_y: usize, flank: Flank) -> f32 {
+        let mut chance = BASE_CRIT_CHANCE;
+
+        if !self.in_cover(a_x, a_y, b_x, b_y) {
+            chance += NO_COVER_CRIT_BONUS * flank.crit_modifier();
+        }
+
+        let closeness = 1.0 - distance(a_x, a_y, b_x, b_y) / CRIT_RANGE;
+        chance + CLOSE_CRIT_BONUS * closeness.max(0.0)
+    }
+
     // Would a unit with a particular sight range be able to see from one tile to another
     // Return the number of tiles away a point is, or none if visibility is blocked
     pub fn line_of_sight(
@@ -136,8 +278,10 @@ impl Tiles {
             let mut distance = 0;
 
             for (a, b) in Bresenham::new(start, end).steps() {
-                // Return if line of sight is blocked by a wall
-                if self.wall_between(a, b) {
+                // Return if line of sight is blocked by a wall or smoke in the way
+                if self.wall_between_points(a, b, Passage::Sight)
+                    || (a != start && self.smoke_at(a))
+                {
                     return None;
                 }
 
@@ -189,8 +333,8 @@ fn unit_visibility() {
 
     // If the unit is boxed into a corner, only it's tile should be visible
 
-    tiles.add_left_wall(29, 0, WallType::Ruin1);
-    tiles.add_top_wall(29, 1, WallType::Ruin2);
+    tiles.add_left_wall(29, 0, WallType::Ruin1, WallMaterial::Brick);
+    tiles.add_top_wall(29, 1, WallType::Ruin2, WallMaterial::Brick);
 
     tiles.update_visibility(&units);
 
@@ -210,10 +354,10 @@ fn unit_visibility() {
 fn line_of_fire() {
     let mut tiles = Tiles::new(5, 5);
 
-    tiles.add_left_wall(1, 0, WallType::Ruin1);
-    tiles.add_top_wall(0, 1, WallType::Ruin1);
-    tiles.add_top_wall(1, 1, WallType::Ruin1);
-    tiles.add_left_wall(1, 1, WallType::Ruin1);
+    tiles.add_left_wall(1, 0, WallType::Ruin1, WallMaterial::Brick);
+    tiles.add_top_wall(0, 1, WallType::Ruin1, WallMaterial::Brick);
+    tiles.add_top_wall(1, 1, WallType::Ruin1, WallMaterial::Brick);
+    tiles.add_left_wall(1, 1, WallType::Ruin1, WallMaterial::Brick);
 
     let top = Some(((1, 0), WallSide::Left));
     let left = Some(((0, 1), WallSide::Top));
@@ -236,3 +380,66 @@ fn line_of_fire() {
     let diag_4 = tiles.line_of_fire(1, 0, 0, 1);
     assert!(diag_4 == right || diag_4 == top);
 }
+
+#[test]
+fn smoke() {
+    let mut tiles = Tiles::new(30, 30);
+    let mut units = Units::new();
+    units.add(UnitType::Squaddie, Side::PlayerA, 0, 0, UnitFacing::Bottom);
+
+    let chance = tiles.chance_to_hit(0, 0, 0, 5);
+    tiles.at_mut(0, 3).smoke = Some(SMOKE_TURNS);
+    tiles.update_visibility(&units);
+
+    // The smoke should be visible but block the tiles behind it
+    assert!(tiles.visibility_at(0, 3, Side::PlayerA).is_visible());
+    assert!(tiles.visibility_at(0, 5, Side::PlayerA).is_invisible());
+
+    // And make shots through it less likely to hit
+    assert!(tiles.chance_to_hit(0, 0, 0, 5) < chance);
+
+    // Until it clears
+    for _ in 0..SMOKE_TURNS {
+        tiles.thin_smoke();
+    }
+
+    tiles.update_visibility(&units);
+    assert!(tiles.visibility_at(0, 5, Side::PlayerA).is_visible());
+}
+
+#[test]
+fn doors_windows_and_low_walls() {
+    let mut tiles = Tiles::new(5, 5);
+    tiles.add_left_wall(1, 0, WallType::Door, WallMaterial::Brick);
+    tiles.add_left_wall(1, 1, WallType::Window, WallMaterial::Brick);
+    tiles.add_left_wall(1, 2, WallType::LowWall, WallMaterial::Brick);
+
+    // Closed doors block everything
+    assert!(!tiles.horizontal_clear(1, 0, Passage::Movement));
+    assert!(tiles.line_of_fire(0, 0, 1, 0).is_some());
+
+    // Until they're opened
+    tiles.at_mut(1, 0).walls.left.as_mut().unwrap().toggle();
+    assert!(tiles.horizontal_clear(1, 0, Passage::Movement));
+    assert!(tiles.line_of_fire(0, 0, 1, 0).is_none());
+
+    // Windows and low walls can be seen and shot through but not walked through
+    for y in 1..3 {
+        assert!(!tiles.horizontal_clear(1, y, Passage::Movement));
+        assert!(tiles.horizontal_clear(1, y, Passage::Sight));
+        assert!(tiles.line_of_fire(0, y, 1, y).is_none());
+    }
+
+    // But only low walls give cover
+    assert!(!tiles.in_cover(3, 1, 0, 1));
+    assert!(tiles.in_cover(3, 2, 0, 2));
+    assert!(tiles.chance_to_hit(3, 2, 0, 2) < tiles.chance_to_hit(3, 1, 0, 1));
+
+    // And make critical hits less likely, as does distance
+    assert!(
+        tiles.crit_chance(3, 2, 0, 2, Flank::Front) < tiles.crit_chance(3, 1, 0, 1, Flank::Front)
+    );
+    assert!(
+        tiles.crit_chance(4, 1, 0, 1, Flank::Front) < tiles.crit_chance(3, 1, 0, 1, Flank::Front)
+    );
+}
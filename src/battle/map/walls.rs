@@ -1,5 +1,7 @@
 use resources::Image;
 
+use std::fmt;
+
 // Walls in-between tiles
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -8,52 +10,185 @@ pub enum WallSide {
     Top,
 }
 
+// What is trying to get past a wall
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Passage {
+    Movement,
+    Sight,
+    Fire,
+}
+
 // The type of wall
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum WallType {
     Ruin1,
     Ruin2,
+    Door,
+    Window,
+    LowWall,
 }
 
 impl WallType {
-    // Get the left image
-    pub fn left_image(&self) -> Image {
+    // How sturdy the wall is compared to a solid wall of the same material
+    pub fn durability(&self) -> f32 {
         match *self {
-            WallType::Ruin1 => Image::Ruin1Left,
-            WallType::Ruin2 => Image::Ruin2Left,
+            WallType::Window => 0.4,
+            WallType::Door => 0.8,
+            _ => 1.0,
         }
     }
 
-    // Get the top image
-    pub fn top_image(&self) -> Image {
-        match *self {
-            WallType::Ruin1 => Image::Ruin1Top,
-            WallType::Ruin2 => Image::Ruin2Top,
+    // Get the image drawn over the material of a side of the wall, if the type has one
+    pub fn overlay(&self, side: WallSide) -> Option<Image> {
+        match (self, side) {
+            (&WallType::Door, WallSide::Left) => Some(Image::DoorLeft),
+            (&WallType::Door, WallSide::Top) => Some(Image::DoorTop),
+            (&WallType::Window, WallSide::Left) => Some(Image::WindowLeft),
+            (&WallType::Window, WallSide::Top) => Some(Image::WindowTop),
+            (&WallType::LowWall, WallSide::Left) => Some(Image::LowWallLeft),
+            (&WallType::LowWall, WallSide::Top) => Some(Image::LowWallTop),
+            _ => None,
         }
     }
+}
 
-    // How much damage the wall can take before it breaks
-    pub fn health(&self) -> i16 {
-        50
+// What a wall is made out of
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum WallMaterial {
+    Brick,
+    Concrete,
+    MetalSheet,
+    WoodenFence,
+}
+
+impl WallMaterial {
+    pub const ALL: [WallMaterial; 4] = [
+        WallMaterial::Brick,
+        WallMaterial::Concrete,
+        WallMaterial::MetalSheet,
+        WallMaterial::WoodenFence,
+    ];
+
+    // How much damage a solid wall of the material can take before it breaks
+    pub fn health(self) -> i16 {
+        match self {
+            WallMaterial::Brick => 50,
+            WallMaterial::Concrete => 80,
+            WallMaterial::MetalSheet => 40,
+            WallMaterial::WoodenFence => 25,
+        }
+    }
+
+    // The fraction of damage that the material shrugs off
+    pub fn resistance(self) -> f32 {
+        match self {
+            WallMaterial::Brick => 0.2,
+            WallMaterial::Concrete => 0.3,
+            WallMaterial::MetalSheet => 0.5,
+            WallMaterial::WoodenFence => 0.0,
+        }
+    }
+}
+
+impl fmt::Display for WallMaterial {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                WallMaterial::Brick => "Brick",
+                WallMaterial::Concrete => "Concrete",
+                WallMaterial::MetalSheet => "Metal Sheet",
+                WallMaterial::WoodenFence => "Wooden Fence",
+            }
+        )
     }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Wall {
     pub tag: WallType,
+    pub material: WallMaterial,
     health: i16,
+    open: bool,
 }
 
 impl Wall {
-    pub fn new(tag: WallType) -> Wall {
-        Wall {
-            health: tag.health(),
+    pub fn new(tag: WallType, material: WallMaterial) -> Wall {
+        let mut wall = Wall {
             tag,
+            material,
+            health: 0,
+            open: false,
+        };
+
+        wall.health = wall.max_health();
+        wall
+    }
+
+    pub fn health(&self) -> i16 {
+        self.health
+    }
+
+    pub fn max_health(&self) -> i16 {
+        (f32::from(self.material.health()) * self.tag.durability()) as i16
+    }
+
+    // Walls look cracked once they've lost half their health
+    pub fn is_cracked(&self) -> bool {
+        self.health <= self.max_health() / 2
+    }
+
+    // Get the image for a side of the wall, using the cracked one if it's damaged
+    pub fn image(&self, side: WallSide) -> Image {
+        match (self.material, side, self.is_cracked()) {
+            (WallMaterial::Brick, WallSide::Left, false) => Image::BrickLeft,
+            (WallMaterial::Brick, WallSide::Top, false) => Image::BrickTop,
+            (WallMaterial::Brick, WallSide::Left, true) => Image::BrickLeftCracked,
+            (WallMaterial::Brick, WallSide::Top, true) => Image::BrickTopCracked,
+            (WallMaterial::Concrete, WallSide::Left, false) => Image::ConcreteLeft,
+            (WallMaterial::Concrete, WallSide::Top, false) => Image::ConcreteTop,
+            (WallMaterial::Concrete, WallSide::Left, true) => Image::ConcreteLeftCracked,
+            (WallMaterial::Concrete, WallSide::Top, true) => Image::ConcreteTopCracked,
+            (WallMaterial::MetalSheet, WallSide::Left, false) => Image::MetalSheetLeft,
+            (WallMaterial::MetalSheet, WallSide::Top, false) => Image::MetalSheetTop,
+            (WallMaterial::MetalSheet, WallSide::Left, true) => Image::MetalSheetLeftCracked,
+            (WallMaterial::MetalSheet, WallSide::Top, true) => Image::MetalSheetTopCracked,
+            (WallMaterial::WoodenFence, WallSide::Left, false) => Image::WoodenFenceLeft,
+            (WallMaterial::WoodenFence, WallSide::Top, false) => Image::WoodenFenceTop,
+            (WallMaterial::WoodenFence, WallSide::Left, true) => Image::WoodenFenceLeftCracked,
+            (WallMaterial::WoodenFence, WallSide::Top, true) => Image::WoodenFenceTopCracked,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    // Open or close a door
+    pub fn toggle(&mut self) {
+        if self.tag == WallType::Door {
+            self.open = !self.open;
+        }
+    }
+
+    // Can something get past the wall
+    pub fn allows(&self, passage: Passage) -> bool {
+        match self.tag {
+            WallType::Door => self.open,
+            WallType::Window | WallType::LowWall => passage != Passage::Movement,
+            _ => false,
         }
     }
 
+    // Does the wall give cover to units behind it
+    pub fn gives_cover(&self) -> bool {
+        self.tag == WallType::LowWall
+    }
+
+    // Damage the wall (reduced by the resistance of its material), returning if it is destroyed
     pub fn damage(&mut self, damage: i16) -> bool {
-        self.health -= damage;
+        self.health -= (f32::from(damage) * (1.0 - self.material.resistance())) as i16;
         self.health <= 0
     }
 }
@@ -72,15 +207,56 @@ impl Walls {
         }
     }
 
-    pub fn set_left(&mut self, tag: WallType) {
+    pub fn get(&self, side: WallSide) -> Option<&Wall> {
+        match side {
+            WallSide::Left => self.left.as_ref(),
+            WallSide::Top => self.top.as_ref(),
+        }
+    }
+
+    pub fn get_mut(&mut self, side: WallSide) -> Option<&mut Wall> {
+        match side {
+            WallSide::Left => self.left.as_mut(),
+            WallSide::Top => self.top.as_mut(),
+        }
+    }
+
+    pub fn set_left(&mut self, tag: WallType, material: WallMaterial) {
         if self.left.is_none() {
-            self.left = Some(Wall::new(tag));
+            self.left = Some(Wall::new(tag, material));
         }
     }
 
-    pub fn set_top(&mut self, tag: WallType) {
+    pub fn set_top(&mut self, tag: WallType, material: WallMaterial) {
         if self.top.is_none() {
-            self.top = Some(Wall::new(tag));
+            self.top = Some(Wall::new(tag, material));
         }
     }
 }
+
+#[test]
+fn wall_materials() {
+    let mut concrete = Wall::new(WallType::Ruin1, WallMaterial::Concrete);
+    let mut fence = Wall::new(WallType::Ruin1, WallMaterial::WoodenFence);
+    assert!(concrete.max_health() > fence.max_health());
+
+    // Concrete should shrug off some of the damage
+    assert!(!concrete.damage(40));
+    assert!(!concrete.is_cracked());
+    assert!(concrete.damage(100));
+
+    // While a fence cracks and then breaks
+    assert!(!fence.damage(15));
+    assert!(fence.is_cracked());
+    assert_eq!(fence.image(WallSide::Top), Image::WoodenFenceTopCracked);
+    assert!(fence.damage(15));
+
+    // Windows are weaker than solid walls
+    let window = Wall::new(WallType::Window, WallMaterial::Brick);
+    assert!(window.max_health() < WallMaterial::Brick.health());
+
+    // And are drawn as the material with a window over it
+    assert_eq!(window.image(WallSide::Left), Image::BrickLeft);
+    assert_eq!(window.tag.overlay(WallSide::Left), Some(Image::WindowLeft));
+    assert_eq!(concrete.tag.overlay(WallSide::Left), None);
+}
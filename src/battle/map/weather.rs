@@ -0,0 +1,134 @@
+// The weather that a skirmish is played in
+
+use utils::lerp;
+
+use std::fmt;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum Weather {
+    Clear,
+    Rain,
+    Fog,
+    Sandstorm,
+}
+
+impl Weather {
+    pub fn rotate_right(&mut self) {
+        *self = match *self {
+            Weather::Clear => Weather::Rain,
+            Weather::Rain => Weather::Fog,
+            Weather::Fog => Weather::Sandstorm,
+            Weather::Sandstorm => Weather::Clear,
+        }
+    }
+
+    pub fn rotate_left(&mut self) {
+        *self = match *self {
+            Weather::Clear => Weather::Sandstorm,
+            Weather::Rain => Weather::Clear,
+            Weather::Fog => Weather::Rain,
+            Weather::Sandstorm => Weather::Fog,
+        }
+    }
+
+    // How much the weather multiplies how far units can see
+    pub fn sight_modifier(self) -> f32 {
+        match self {
+            Weather::Clear => 1.0,
+            Weather::Rain => 0.8,
+            Weather::Fog => 0.5,
+            Weather::Sandstorm => 0.6,
+        }
+    }
+
+    // How much the weather multiplies the chance to hit
+    pub fn hit_modifier(self) -> f32 {
+        match self {
+            Weather::Clear | Weather::Fog => 1.0,
+            Weather::Rain => 0.9,
+            Weather::Sandstorm => 0.75,
+        }
+    }
+
+    // How much the weather multiplies how far away noises can be heard
+    pub fn hearing_modifier(self) -> f32 {
+        match self {
+            Weather::Clear => 1.0,
+            Weather::Rain => 0.6,
+            Weather::Fog => 1.2,
+            Weather::Sandstorm => 0.5,
+        }
+    }
+
+    // The colour that the weather tints the map with, if any
+    fn colour(self) -> Option<[f32; 4]> {
+        match self {
+            Weather::Clear => None,
+            Weather::Rain => Some([0.1, 0.2, 0.5, 0.2]),
+            Weather::Fog => Some([0.7, 0.7, 0.7, 0.35]),
+            Weather::Sandstorm => Some([0.75, 0.55, 0.25, 0.35]),
+        }
+    }
+
+    // Blend the weather's colour into a tile's overlay colour
+    pub fn tint(self, overlay: [f32; 4]) -> [f32; 4] {
+        match self.colour() {
+            Some([red, green, blue, alpha]) => {
+                // The weather shows through whatever the overlay doesn't cover
+                let weather_alpha = alpha * (1.0 - overlay[3]);
+                let total_alpha = overlay[3] + weather_alpha;
+                let weight = weather_alpha / total_alpha;
+
+                [
+                    lerp(overlay[0], red, weight),
+                    lerp(overlay[1], green, weight),
+                    lerp(overlay[2], blue, weight),
+                    total_alpha,
+                ]
+            }
+            None => overlay,
+        }
+    }
+}
+
+impl fmt::Display for Weather {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                Weather::Clear => "Clear",
+                Weather::Rain => "Rain",
+                Weather::Fog => "Fog",
+                Weather::Sandstorm => "Sandstorm",
+            }
+        )
+    }
+}
+
+#[test]
+fn weather_effects() {
+    use super::super::units::*;
+    use super::Map;
+
+    let mut map = Map::new(30, 30, 1.0);
+    map.units
+        .add(UnitType::Squaddie, Side::PlayerA, 0, 0, UnitFacing::Bottom);
+    map.tiles.update_visibility(&map.units);
+
+    let chance = map.tiles.chance_to_hit(0, 0, 0, 5);
+    assert!(map.tiles.visibility_at(6, 0, Side::PlayerA).is_visible());
+
+    // Sandstorms should cut down how far units can see and how well they can shoot
+    map.set_weather(Weather::Sandstorm);
+    map.tiles.update_visibility(&map.units);
+    assert!(!map.tiles.visibility_at(6, 0, Side::PlayerA).is_visible());
+    assert!(map.tiles.chance_to_hit(0, 0, 0, 5) < chance);
+
+    // Tints shouldn't be any more opaque than fully covered
+    assert_eq!(Weather::Fog.tint([0.0, 0.0, 0.0, 1.0])[3], 1.0);
+    assert_eq!(
+        Weather::Clear.tint([0.0, 0.0, 0.0, 0.5]),
+        [0.0, 0.0, 0.0, 0.5]
+    );
+}
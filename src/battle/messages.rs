@@ -1,3 +1,4 @@
+use super::abilities::*;
 use super::map::*;
 use super::paths::*;
 use super::responses::*;
@@ -7,13 +8,23 @@ use super::units::*;
 pub enum ClientMessage {
     EndTurn,
     SaveGame(String),
-    Command { unit: u8, command: Command },
+    Command {
+        unit: u8,
+        command: Command,
+    },
+    Deploy {
+        unit: u8,
+        x: usize,
+        y: usize,
+        facing: UnitFacing,
+    },
+    ConfirmDeployment,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum ServerMessage {
     Responses(Vec<Response>),
-    InitialState { map: Map, side: Side },
+    InitialState { map: Box<Map>, side: Side },
     GameFull,
 }
 
@@ -21,7 +32,7 @@ impl ServerMessage {
     pub fn initial_state(map: &mut Map, side: Side) -> Self {
         ServerMessage::InitialState {
             side,
-            map: map.clone_visible(side),
+            map: Box::new(map.clone_visible(side)),
         }
     }
 }
@@ -29,12 +40,47 @@ impl ServerMessage {
 #[derive(Debug, Serialize, Deserialize)]
 pub enum Command {
     Walk(Vec<UnitFacing>),
-    Fire { x: usize, y: usize },
+    Fire {
+        x: usize,
+        y: usize,
+    },
     Turn(UnitFacing),
     DropItem(usize),
     PickupItem(usize),
     UseItem(usize),
-    ThrowItem { item: usize, x: usize, y: usize },
+    UseItemOn {
+        item: usize,
+        target: u8,
+    },
+    GiveItem {
+        item: usize,
+        target: u8,
+    },
+    SalvageItem(usize),
+    ThrowItem {
+        item: usize,
+        x: usize,
+        y: usize,
+    },
+    ToggleDoor {
+        x: usize,
+        y: usize,
+    },
+    DeployItem {
+        item: usize,
+        x: usize,
+        y: usize,
+    },
+    UseAbility {
+        ability: Ability,
+        x: usize,
+        y: usize,
+    },
+    Drag {
+        target: u8,
+        x: usize,
+        y: usize,
+    },
 }
 
 impl Command {
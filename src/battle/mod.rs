@@ -1,11 +1,14 @@
 // A battle in the game
 
+mod abilities;
 mod ai;
 mod commands;
 mod drawer;
+pub mod loadouts;
 pub mod map;
 mod messages;
 mod networking;
+pub mod objectives;
 mod paths;
 mod responses;
 mod ui;
@@ -13,6 +16,7 @@ pub mod units;
 
 use *;
 
+use self::abilities::*;
 use self::drawer::*;
 use self::map::*;
 use self::networking::*;
@@ -53,6 +57,10 @@ pub struct Battle {
     keys: Keys,
     interface: Interface,
     visual_debugging: bool,
+    // The ability waiting for a tile to be clicked on
+    targeting: Option<Ability>,
+    // How long the battle has been running for, used for animating tiles
+    time: f32,
 }
 
 impl Battle {
@@ -78,6 +86,8 @@ impl Battle {
             path: None,
             interface: Interface::new(),
             visual_debugging: false,
+            targeting: None,
+            time: 0.0,
         }
     }
 
@@ -152,6 +162,24 @@ impl Battle {
             VirtualKeyCode::P => self.keys.zoom_in = pressed,
             VirtualKeyCode::LControl | VirtualKeyCode::RControl => self.keys.force_fire = pressed,
             VirtualKeyCode::I if pressed => self.interface.toggle_inventory(),
+            // Open or close the door between the selected unit and the cursor
+            VirtualKeyCode::F if pressed => {
+                if let (Some(selected), Some((x, y))) = (self.selected, self.cursor) {
+                    self.client.toggle_door(selected, x, y);
+                }
+            }
+            // Drag an adjacent downed ally while stepping onto the tile under the cursor
+            VirtualKeyCode::C if pressed => {
+                if let (Some(unit), Some((x, y))) = (self.selected(), self.cursor) {
+                    let downed = self.map().units.iter().find(|ally| {
+                        ally.side == unit.side && ally.is_downed() && unit.adjacent_to(ally)
+                    });
+
+                    if let Some(downed) = downed {
+                        self.client.drag(unit.id, downed.id, x, y);
+                    }
+                }
+            }
             VirtualKeyCode::Grave if pressed => self.visual_debugging = !self.visual_debugging,
             _ => {}
         }
@@ -161,6 +189,8 @@ impl Battle {
 
     // Update the battle
     pub fn update(&mut self, ctx: &mut Context, dt: f32) {
+        self.time += dt;
+
         // Move the camera
         if self.keys.up {
             self.camera.move_y(dt, &self.client.map);
@@ -225,6 +255,20 @@ impl Battle {
     }
 
     fn perform_actions(&mut self, x: usize, y: usize) {
+        // Select a unit or move the selected one during deployment
+        if self.client.deploying() {
+            match self.client.map.units.at(x, y) {
+                Some(unit) if unit.side == self.client.side => self.selected = Some(unit.id),
+                _ => {
+                    if let Some(unit) = self.selected() {
+                        self.client.deploy(unit.id, x, y, unit.facing);
+                    }
+                }
+            }
+
+            return;
+        }
+
         match self.client.map.units.at(x, y) {
             Some(unit) => {
                 self.path = None;
@@ -277,6 +321,16 @@ impl Battle {
                 Some(ButtonType::Inventory) => self.interface.toggle_inventory(),
                 // Toggle the save game input
                 Some(ButtonType::SaveGame) => self.interface.toggle_save_game(),
+                // Use an ability
+                Some(ButtonType::Ability(ability)) => self.use_ability(ability),
+                // Use the ability being targeted on the tile under the cursor
+                _ if self.targeting.is_some() => {
+                    if let (Some(ability), Some(selected), Some((x, y))) =
+                        (self.targeting.take(), self.selected, self.cursor)
+                    {
+                        self.client.use_ability(selected, ability, x, y);
+                    }
+                }
                 // Or select/deselect a unit
                 _ => {
                     if let Some((x, y)) = self.cursor {
@@ -287,8 +341,13 @@ impl Battle {
             MouseButton::Right => {
                 if let Some((x, y)) = self.cursor {
                     if let Some(unit) = self.selected() {
-                        self.client
-                            .turn(unit.id, UnitFacing::from_points(unit.x, unit.y, x, y));
+                        let facing = UnitFacing::from_points(unit.x, unit.y, x, y);
+
+                        if self.client.deploying() {
+                            self.client.deploy(unit.id, unit.x, unit.y, facing);
+                        } else {
+                            self.client.turn(unit.id, facing);
+                        }
                     }
                 }
             }
@@ -301,7 +360,23 @@ impl Battle {
     }
 
     fn waiting_for_command(&self) -> bool {
-        self.client.our_turn() && self.client.responses().is_empty()
+        (self.client.our_turn() || self.client.deploying()) && self.client.responses().is_empty()
+    }
+
+    // Use an ability straight away or wait for a tile to be clicked on
+    fn use_ability(&mut self, ability: Ability) {
+        let target = match self.selected() {
+            Some(unit) => match ability.target() {
+                AbilityTarget::User => Some((unit.id, unit.x, unit.y)),
+                AbilityTarget::Tile(_) => None,
+            },
+            None => return,
+        };
+
+        match target {
+            Some((id, x, y)) => self.client.use_ability(id, ability, x, y),
+            None => self.targeting = Some(ability),
+        }
     }
 
     // Get a reference to the unit that is selected
@@ -319,10 +394,14 @@ impl Battle {
                 .unwrap_or(false)
     }
 
-    // End the current turn
+    // End the turn, or confirm where the units are during deployment
     fn end_turn(&mut self) {
         if self.waiting_for_command() {
-            self.client.end_turn();
+            if self.client.deploying() {
+                self.client.confirm_deployment();
+            } else {
+                self.client.end_turn();
+            }
         }
     }
 }
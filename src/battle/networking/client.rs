@@ -16,7 +16,7 @@ impl Client {
     pub fn new(mut connection: ClientConn) -> Result<Self> {
         let initial_state = connection.recv_blocking()?;
         let (map, side) = match initial_state {
-            ServerMessage::InitialState { map, side } => (map, side),
+            ServerMessage::InitialState { map, side } => (*map, side),
             ServerMessage::GameFull => return Err("Game full".into()),
             message => {
                 return Err(format!(
@@ -92,7 +92,10 @@ impl Client {
 
         for response in self.response_queue.drain(..) {
             match response {
-                Response::NewState(map) => self.map = map,
+                Response::NewState(map) => self.map.update_from(*map, self.side),
+                Response::Noise(noise) => {
+                    self.map.hear(noise);
+                }
                 Response::GameOver(_) => return (true, invalid_command),
                 Response::InvalidCommand => invalid_command = true,
                 _ => {}
@@ -132,6 +135,18 @@ impl Client {
         self.send_command(unit, Command::UseItem(item));
     }
 
+    pub fn use_item_on(&self, unit: u8, item: usize, target: u8) {
+        self.send_command(unit, Command::UseItemOn { item, target });
+    }
+
+    pub fn give_item(&self, unit: u8, item: usize, target: u8) {
+        self.send_command(unit, Command::GiveItem { item, target });
+    }
+
+    pub fn salvage_item(&self, unit: u8, item: usize) {
+        self.send_command(unit, Command::SalvageItem(item));
+    }
+
     pub fn drop_item(&self, unit: u8, item: usize) {
         self.send_command(unit, Command::DropItem(item));
     }
@@ -144,6 +159,38 @@ impl Client {
         self.send_command(unit, Command::ThrowItem { item, x, y });
     }
 
+    pub fn deploy_item(&self, unit: u8, item: usize, x: usize, y: usize) {
+        self.send_command(unit, Command::DeployItem { item, x, y });
+    }
+
+    pub fn use_ability(&self, unit: u8, ability: Ability, x: usize, y: usize) {
+        self.send_command(unit, Command::UseAbility { ability, x, y });
+    }
+
+    pub fn toggle_door(&self, unit: u8, x: usize, y: usize) {
+        self.send_command(unit, Command::ToggleDoor { x, y });
+    }
+
+    pub fn drag(&self, unit: u8, target: u8, x: usize, y: usize) {
+        self.send_command(unit, Command::Drag { target, x, y });
+    }
+
+    pub fn deploy(&self, unit: u8, x: usize, y: usize, facing: UnitFacing) {
+        self.connection
+            .send(ClientMessage::Deploy { unit, x, y, facing })
+            .unwrap();
+    }
+
+    pub fn confirm_deployment(&self) {
+        self.connection
+            .send(ClientMessage::ConfirmDeployment)
+            .unwrap();
+    }
+
+    pub fn deploying(&self) -> bool {
+        self.map.deployment.is_active()
+    }
+
     pub fn end_turn(&self) {
         self.connection.send(ClientMessage::EndTurn).unwrap();
     }
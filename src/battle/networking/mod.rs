@@ -2,6 +2,7 @@ use std::net::*;
 use std::thread::{sleep, spawn, JoinHandle};
 use std::time::*;
 
+use super::abilities::*;
 use super::ai::*;
 use super::drawer::*;
 use super::map::*;
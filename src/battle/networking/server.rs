@@ -100,38 +100,34 @@ impl Server {
 
                 let mut game_over = false;
 
-                match self.map.side {
-                    Side::PlayerA => {
-                        while let Ok(message) = player_a.recv() {
-                            game_over |= handle_message(
-                                Side::PlayerA,
-                                &mut self.map,
-                                &player_a,
-                                &player_b,
-                                &self.settings,
-                                message,
-                            );
-                        }
-
-                        while let Ok(_) = player_b.recv() {
-                            // Do nothing
-                        }
+                // Both sides can send messages while deploying, otherwise only the side whose turn it is can
+                let deploying = self.map.deployment.is_active();
+                let listen_to_a = deploying || self.map.side == Side::PlayerA;
+                let listen_to_b = deploying || self.map.side == Side::PlayerB;
+
+                while let Ok(message) = player_a.recv() {
+                    if listen_to_a {
+                        game_over |= handle_message(
+                            Side::PlayerA,
+                            &mut self.map,
+                            &player_a,
+                            &player_b,
+                            &self.settings,
+                            message,
+                        );
                     }
-                    Side::PlayerB => {
-                        while let Ok(message) = player_b.recv() {
-                            game_over |= handle_message(
-                                Side::PlayerB,
-                                &mut self.map,
-                                &player_a,
-                                &player_b,
-                                &self.settings,
-                                message,
-                            );
-                        }
-
-                        while let Ok(_) = player_a.recv() {
-                            // Do nothing
-                        }
+                }
+
+                while let Ok(message) = player_b.recv() {
+                    if listen_to_b {
+                        game_over |= handle_message(
+                            Side::PlayerB,
+                            &mut self.map,
+                            &player_a,
+                            &player_b,
+                            &self.settings,
+                            message,
+                        );
                     }
                 }
 
@@ -158,10 +154,11 @@ fn handle_message(
 
     let (player_a_responses, player_b_responses) = map.handle_message(message, settings, side);
 
-    for response in &player_a_responses {
-        if let Response::GameOver(_) = response {
-            game_over = true;
-        }
+    if player_a_responses
+        .iter()
+        .any(|response| response.as_game_over().is_some())
+    {
+        game_over = true;
     }
 
     if !player_a_responses.is_empty() {
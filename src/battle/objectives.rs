@@ -0,0 +1,465 @@
+// Mission objectives that can end a battle other than by wiping out a side.
+// Objectives are always player A's to complete, player B has to stop them.
+
+use std::fmt;
+
+use super::commands::*;
+use super::map::*;
+use super::units::*;
+use items::Item;
+
+// How many turns a point has to be held or a VIP kept alive for
+pub const OBJECTIVE_TURNS: u16 = 5;
+// How many rows deep extraction zones are
+const ZONE_DEPTH: usize = 2;
+
+// The type of objective that a skirmish is played with
+#[derive(Copy, Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum ObjectiveType {
+    Annihilation,
+    Extract,
+    Hold,
+    Retrieve,
+    ProtectVIP,
+}
+
+impl ObjectiveType {
+    pub fn rotate_right(&mut self) {
+        *self = match *self {
+            ObjectiveType::Annihilation => ObjectiveType::Extract,
+            ObjectiveType::Extract => ObjectiveType::Hold,
+            ObjectiveType::Hold => ObjectiveType::Retrieve,
+            ObjectiveType::Retrieve => ObjectiveType::ProtectVIP,
+            ObjectiveType::ProtectVIP => ObjectiveType::Annihilation,
+        }
+    }
+
+    pub fn rotate_left(&mut self) {
+        *self = match *self {
+            ObjectiveType::Annihilation => ObjectiveType::ProtectVIP,
+            ObjectiveType::Extract => ObjectiveType::Annihilation,
+            ObjectiveType::Hold => ObjectiveType::Extract,
+            ObjectiveType::Retrieve => ObjectiveType::Hold,
+            ObjectiveType::ProtectVIP => ObjectiveType::Retrieve,
+        }
+    }
+}
+
+impl fmt::Display for ObjectiveType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                ObjectiveType::Annihilation => "Annihilation",
+                ObjectiveType::Extract => "Extract",
+                ObjectiveType::Hold => "Hold",
+                ObjectiveType::Retrieve => "Retrieve",
+                ObjectiveType::ProtectVIP => "Protect VIP",
+            }
+        )
+    }
+}
+
+// Whether an objective has been completed or failed yet
+#[derive(Copy, Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum ObjectiveStatus {
+    InProgress,
+    Completed,
+    Failed,
+}
+
+impl fmt::Display for ObjectiveStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                ObjectiveStatus::InProgress => "In Progress",
+                ObjectiveStatus::Completed => "Completed",
+                ObjectiveStatus::Failed => "Failed",
+            }
+        )
+    }
+}
+
+// A rectangular zone of tiles
+#[derive(Copy, Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct Zone {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+}
+
+impl Zone {
+    pub fn new(x: usize, y: usize, width: usize, height: usize) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    // The rows at the top of the map, where player A starts
+    fn top(map: &Map) -> Self {
+        Self::new(0, 0, map.tiles.width(), ZONE_DEPTH)
+    }
+
+    // The rows at the bottom of the map, where player B starts
+    fn bottom(map: &Map) -> Self {
+        let height = map.tiles.height();
+        Self::new(0, height - ZONE_DEPTH, map.tiles.width(), ZONE_DEPTH)
+    }
+
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+}
+
+// The points a side has earned, used to decide the winner if the turn limit is reached
+#[derive(Copy, Clone, Serialize, Deserialize, Debug, PartialEq, Default)]
+pub struct Score {
+    pub kills: u8,
+    pub damage: u16,
+    pub objective: u16,
+}
+
+impl Score {
+    const KILL_POINTS: u32 = 100;
+    const OBJECTIVE_POINTS: u32 = 50;
+
+    // Record damage dealt to an enemy unit
+    pub fn record_damage(&mut self, damage: i16, lethal: bool) {
+        self.damage = self.damage.saturating_add(damage.max(0) as u16);
+
+        if lethal {
+            self.kills += 1;
+        }
+    }
+
+    pub fn points(&self) -> u32 {
+        u32::from(self.kills) * Self::KILL_POINTS
+            + u32::from(self.damage)
+            + u32::from(self.objective) * Self::OBJECTIVE_POINTS
+    }
+}
+
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} (Kills: {}, Damage: {}, Objective: {})",
+            self.points(),
+            self.kills,
+            self.damage,
+            self.objective
+        )
+    }
+}
+
+// The scores of both sides
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Default)]
+pub struct Scores {
+    player_a: Score,
+    player_b: Score,
+}
+
+impl Scores {
+    pub fn get(&self, side: Side) -> Score {
+        match side {
+            Side::PlayerA => self.player_a,
+            Side::PlayerB => self.player_b,
+        }
+    }
+
+    pub fn get_mut(&mut self, side: Side) -> &mut Score {
+        match side {
+            Side::PlayerA => &mut self.player_a,
+            Side::PlayerB => &mut self.player_b,
+        }
+    }
+}
+
+// The state of the objective of the battle
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum Objective {
+    // Kill all the enemy units
+    Annihilation,
+    // Get a number of units into the extraction zone
+    Extract {
+        zone: Zone,
+        required: u8,
+        extracted: u8,
+    },
+    // Keep a unit on a point for a number of turns in a row
+    Hold {
+        x: usize,
+        y: usize,
+        held: u16,
+    },
+    // Pick up the intel and carry it back to the starting zone
+    Retrieve {
+        zone: Zone,
+    },
+    // Keep the VIP alive for a number of turns
+    ProtectVIP {
+        vip: u8,
+        survived: u16,
+    },
+}
+
+impl Objective {
+    // Create a new objective and set up the map for it
+    pub fn new(tag: ObjectiveType, map: &mut Map) -> Self {
+        let (center_x, center_y) = (map.tiles.width() / 2, map.tiles.height() / 2);
+
+        match tag {
+            ObjectiveType::Annihilation => Objective::Annihilation,
+            ObjectiveType::Extract => Objective::Extract {
+                zone: Zone::bottom(map),
+                // At least half of the units need to get out
+                required: {
+                    let units = map.units.count(Side::PlayerA);
+                    units - units / 2
+                },
+                extracted: 0,
+            },
+            ObjectiveType::Hold => {
+                // Make sure the point can be stood on
+                map.tiles.at_mut(center_x, center_y).obstacle = Obstacle::Empty;
+
+                Objective::Hold {
+                    x: center_x,
+                    y: center_y,
+                    held: 0,
+                }
+            }
+            ObjectiveType::Retrieve => {
+                // Put the intel on the enemy half of the map
+                let intel_y = center_y + center_y / 2;
+                map.tiles.at_mut(center_x, intel_y).obstacle = Obstacle::Empty;
+                map.tiles.drop(center_x, intel_y, Item::Intel);
+
+                Objective::Retrieve {
+                    zone: Zone::top(map),
+                }
+            }
+            ObjectiveType::ProtectVIP => Objective::ProtectVIP {
+                vip: map
+                    .units
+                    .iter()
+                    .filter(|unit| unit.side == Side::PlayerA)
+                    .map(|unit| unit.id)
+                    .min()
+                    .unwrap_or(0),
+                survived: 0,
+            },
+        }
+    }
+
+    pub fn tag(&self) -> ObjectiveType {
+        match *self {
+            Objective::Annihilation => ObjectiveType::Annihilation,
+            Objective::Extract { .. } => ObjectiveType::Extract,
+            Objective::Hold { .. } => ObjectiveType::Hold,
+            Objective::Retrieve { .. } => ObjectiveType::Retrieve,
+            Objective::ProtectVIP { .. } => ObjectiveType::ProtectVIP,
+        }
+    }
+
+    // How many units have been extracted from the map
+    pub fn extracted(&self) -> u8 {
+        match *self {
+            Objective::Extract { extracted, .. } => extracted,
+            _ => 0,
+        }
+    }
+
+    // Is the unit with the given ID the VIP
+    pub fn is_vip(&self, id: u8) -> bool {
+        match *self {
+            Objective::ProtectVIP { vip, .. } => vip == id,
+            _ => false,
+        }
+    }
+
+    // Which side (if any) is currently holding the objective
+    pub fn held_by(&self, units: &Units) -> Option<Side> {
+        match *self {
+            Objective::Hold { x, y, .. } => units.at(x, y).map(|unit| unit.side),
+            Objective::Retrieve { .. } => units
+                .iter()
+                .find(|unit| unit.inventory().contains(&Item::Intel))
+                .map(|unit| unit.side),
+            _ => None,
+        }
+    }
+
+    // Should a tile be marked on the map as part of the objective
+    pub fn marks(&self, x: usize, y: usize) -> bool {
+        match *self {
+            Objective::Extract { ref zone, .. } | Objective::Retrieve { ref zone } => {
+                zone.contains(x, y)
+            }
+            Objective::Hold {
+                x: point_x,
+                y: point_y,
+                ..
+            } => x == point_x && y == point_y,
+            _ => false,
+        }
+    }
+
+    // Update the objective at the end of a side's turn
+    pub fn end_turn(
+        &mut self,
+        side: Side,
+        units: &mut Units,
+        tiles: &mut Tiles,
+        responses: &mut ServerResponses,
+    ) {
+        match *self {
+            // Extract the player A units that finished their turn in the zone
+            Objective::Extract {
+                ref zone,
+                ref mut extracted,
+                ..
+            } if side == Side::PlayerA => {
+                let in_zone: Vec<u8> = units
+                    .iter()
+                    .filter(|unit| unit.side == Side::PlayerA && zone.contains(unit.x, unit.y))
+                    .map(|unit| unit.id)
+                    .collect();
+
+                for id in in_zone {
+                    units.remove(tiles, id);
+                    *extracted += 1;
+                }
+
+                if *extracted > 0 {
+                    responses.push_message(format!("{} units extracted", extracted));
+                }
+            }
+            // At the end of each round, check if the point is being held
+            Objective::Hold {
+                x, y, ref mut held, ..
+            } if side == Side::PlayerB => {
+                if units.on_side(x, y, Side::PlayerA) {
+                    *held += 1;
+                } else {
+                    *held = 0;
+                }
+            }
+            Objective::ProtectVIP {
+                ref mut survived, ..
+            } if side == Side::PlayerB => *survived += 1,
+            _ => {}
+        }
+    }
+
+    // Get whether the objective has been completed or failed
+    pub fn status(&self, units: &Units) -> ObjectiveStatus {
+        match *self {
+            Objective::Extract {
+                required,
+                extracted,
+                ..
+            } => {
+                if extracted >= required {
+                    ObjectiveStatus::Completed
+                } else if extracted + units.count(Side::PlayerA) < required {
+                    ObjectiveStatus::Failed
+                } else {
+                    ObjectiveStatus::InProgress
+                }
+            }
+            Objective::Hold { held, .. } if held >= OBJECTIVE_TURNS => ObjectiveStatus::Completed,
+            Objective::Retrieve { ref zone } => {
+                let retrieved = units.iter().any(|unit| {
+                    unit.side == Side::PlayerA
+                        && zone.contains(unit.x, unit.y)
+                        && unit.inventory().contains(&Item::Intel)
+                });
+
+                if retrieved {
+                    ObjectiveStatus::Completed
+                } else {
+                    ObjectiveStatus::InProgress
+                }
+            }
+            Objective::ProtectVIP { vip, survived } => {
+                if units.get(vip).is_none() {
+                    ObjectiveStatus::Failed
+                } else if survived >= OBJECTIVE_TURNS {
+                    ObjectiveStatus::Completed
+                } else {
+                    ObjectiveStatus::InProgress
+                }
+            }
+            _ => ObjectiveStatus::InProgress,
+        }
+    }
+
+    // A line describing the objective and its progress
+    pub fn info(&self) -> String {
+        match *self {
+            Objective::Annihilation => "Objective: Kill all enemy units".into(),
+            Objective::Extract {
+                required,
+                extracted,
+                ..
+            } => format!(
+                "Objective: Extract {} units ({}/{})",
+                required, extracted, required
+            ),
+            Objective::Hold { x, y, held } => format!(
+                "Objective: Hold ({}, {}) for {} turns ({}/{})",
+                x, y, OBJECTIVE_TURNS, held, OBJECTIVE_TURNS
+            ),
+            Objective::Retrieve { .. } => "Objective: Retrieve the intel".into(),
+            Objective::ProtectVIP { survived, .. } => format!(
+                "Objective: Keep the VIP alive for {} turns ({}/{})",
+                OBJECTIVE_TURNS, survived, OBJECTIVE_TURNS
+            ),
+        }
+    }
+}
+
+#[test]
+fn objective_progress() {
+    let mut map = Map::new(10, 10, 1.0);
+    map.units
+        .add(UnitType::Squaddie, Side::PlayerA, 0, 0, UnitFacing::Bottom);
+    map.units
+        .add(UnitType::Squaddie, Side::PlayerA, 1, 9, UnitFacing::Bottom);
+    map.units
+        .add(UnitType::Machine, Side::PlayerB, 9, 0, UnitFacing::Bottom);
+
+    // One of the two units should need to be extracted
+
+    let mut objective = Objective::new(ObjectiveType::Extract, &mut map);
+    assert_eq!(objective.status(&map.units), ObjectiveStatus::InProgress);
+
+    let mut responses = ServerResponses::new();
+    objective.end_turn(
+        Side::PlayerA,
+        &mut map.units,
+        &mut map.tiles,
+        &mut responses,
+    );
+
+    assert_eq!(objective.extracted(), 1);
+    assert_eq!(map.units.count(Side::PlayerA), 1);
+    assert_eq!(objective.status(&map.units), ObjectiveStatus::Completed);
+
+    // The VIP should be the first player A unit, and the objective fails if it dies
+
+    let objective = Objective::new(ObjectiveType::ProtectVIP, &mut map);
+    assert!(objective.is_vip(0));
+    assert_eq!(objective.status(&map.units), ObjectiveStatus::InProgress);
+
+    map.units.kill(&mut map.tiles, 0);
+    assert_eq!(objective.status(&map.units), ObjectiveStatus::Failed);
+}
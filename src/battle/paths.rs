@@ -2,7 +2,7 @@
 
 use pathfinding::directed::astar::astar;
 
-use super::map::Map;
+use super::map::{Map, Passage};
 use super::units::{Unit, UnitFacing};
 
 // Use the A Star algorithm to find a path between a unit and a destination
@@ -12,13 +12,13 @@ pub fn pathfind(
     dest_y: usize,
     map: &Map,
 ) -> Option<(Vec<PathPoint>, u16)> {
-    if map.taken(dest_x, dest_y) {
+    if map.taken(dest_x, dest_y) || unit.tag.stationary() {
         return None;
     }
 
     astar(
         &PathPoint::from(unit),
-        |point| point.neighbours(map),
+        |point| point.neighbours(map, unit.encumbrance()),
         |point| point.cost(dest_x, dest_y),
         |point| point.at(dest_x, dest_y),
     )
@@ -68,55 +68,71 @@ impl PathPoint {
         }
     }
 
-    // Get the neighbours to a point
-    pub fn neighbours(&self, map: &Map) -> Vec<(PathPoint, u16)> {
+    // Get the neighbours to a point, with walking costing more the more encumbered the unit is
+    pub fn neighbours(&self, map: &Map, encumbrance: f32) -> Vec<(PathPoint, u16)> {
         let mut neighbours = Vec::new();
 
         let tiles = &map.tiles;
 
         // lateral movement
 
-        if self.x > 0 && tiles.horizontal_clear(self.x, self.y) {
-            self.add_point(&mut neighbours, map, self.x - 1, self.y);
+        if self.x > 0 && tiles.horizontal_clear(self.x, self.y, Passage::Movement) {
+            self.add_point(&mut neighbours, map, encumbrance, self.x - 1, self.y);
         }
 
-        if self.x < tiles.width() - 1 && tiles.horizontal_clear(self.x + 1, self.y) {
-            self.add_point(&mut neighbours, map, self.x + 1, self.y);
+        if self.x < tiles.width() - 1
+            && tiles.horizontal_clear(self.x + 1, self.y, Passage::Movement)
+        {
+            self.add_point(&mut neighbours, map, encumbrance, self.x + 1, self.y);
         }
 
-        if self.y > 0 && tiles.vertical_clear(self.x, self.y) {
-            self.add_point(&mut neighbours, map, self.x, self.y - 1);
+        if self.y > 0 && tiles.vertical_clear(self.x, self.y, Passage::Movement) {
+            self.add_point(&mut neighbours, map, encumbrance, self.x, self.y - 1);
         }
 
-        if self.y < tiles.height() - 1 && tiles.vertical_clear(self.x, self.y + 1) {
-            self.add_point(&mut neighbours, map, self.x, self.y + 1);
+        if self.y < tiles.height() - 1
+            && tiles.vertical_clear(self.x, self.y + 1, Passage::Movement)
+        {
+            self.add_point(&mut neighbours, map, encumbrance, self.x, self.y + 1);
         }
 
         // Diagonal movement
 
-        if tiles.diagonal_clear(self.x, self.y, true) {
-            self.add_point(&mut neighbours, map, self.x - 1, self.y - 1);
+        if tiles.diagonal_clear(self.x, self.y, true, Passage::Movement) {
+            self.add_point(&mut neighbours, map, encumbrance, self.x - 1, self.y - 1);
         }
 
-        if tiles.diagonal_clear(self.x + 1, self.y, false) {
-            self.add_point(&mut neighbours, map, self.x + 1, self.y - 1);
+        if tiles.diagonal_clear(self.x + 1, self.y, false, Passage::Movement) {
+            self.add_point(&mut neighbours, map, encumbrance, self.x + 1, self.y - 1);
         }
 
-        if tiles.diagonal_clear(self.x, self.y + 1, false) {
-            self.add_point(&mut neighbours, map, self.x - 1, self.y + 1);
+        if tiles.diagonal_clear(self.x, self.y + 1, false, Passage::Movement) {
+            self.add_point(&mut neighbours, map, encumbrance, self.x - 1, self.y + 1);
         }
 
-        if tiles.diagonal_clear(self.x + 1, self.y + 1, true) {
-            self.add_point(&mut neighbours, map, self.x + 1, self.y + 1);
+        if tiles.diagonal_clear(self.x + 1, self.y + 1, true, Passage::Movement) {
+            self.add_point(&mut neighbours, map, encumbrance, self.x + 1, self.y + 1);
         }
 
         neighbours
     }
 
-    // Add a point the the neighbours if it's not taken
-    fn add_point(&self, neighbours: &mut Vec<(PathPoint, u16)>, map: &Map, x: usize, y: usize) {
-        if !map.taken(x, y) {
-            let cost = self.cost(x, y);
+    // Add a point the the neighbours if it's not taken and not too high to climb up to
+    fn add_point(
+        &self,
+        neighbours: &mut Vec<(PathPoint, u16)>,
+        map: &Map,
+        encumbrance: f32,
+        x: usize,
+        y: usize,
+    ) {
+        if map.taken(x, y) {
+            return;
+        }
+
+        if let Some(climb_cost) = map.tiles.climb_cost(self.x, self.y, x, y) {
+            let walk_cost = f32::from(self.cost(x, y)) * (1.0 + encumbrance);
+            let cost = walk_cost.round() as u16 + climb_cost;
             let facing = UnitFacing::from_points(self.x, self.y, x, y);
             neighbours.push((PathPoint::new(x, y, cost, facing), cost));
         }
@@ -159,13 +175,15 @@ fn pathfinding() {
 
     // The path should work fine if it's blocked on one side
 
-    map.tiles.add_left_wall(1, 0, WallType::Ruin1);
+    map.tiles
+        .add_left_wall(1, 0, WallType::Ruin1, WallMaterial::Brick);
 
     assert_eq!(pathfind(&unit, size - 1, size - 1, &map), path);
 
     // But not both
 
-    map.tiles.add_top_wall(0, 1, WallType::Ruin1);
+    map.tiles
+        .add_top_wall(0, 1, WallType::Ruin1, WallMaterial::Brick);
 
     assert_eq!(pathfind(&unit, size - 1, size - 1, &map), None);
 }
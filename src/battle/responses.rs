@@ -3,6 +3,7 @@
 
 use super::drawer::*;
 use super::map::*;
+use super::objectives::*;
 use super::ui::*;
 use super::units::*;
 
@@ -16,6 +17,7 @@ use weapons::*;
 
 use std::collections::*;
 use std::f32::consts::PI;
+use std::fmt;
 
 pub struct VisibleEnemies {
     positions: HashMap<u8, (usize, usize)>,
@@ -47,6 +49,11 @@ pub struct GameStats {
     pub won: bool,
     pub units_lost: u8,
     pub units_killed: u8,
+    pub objective: ObjectiveType,
+    pub objective_status: ObjectiveStatus,
+    pub turn_limit_reached: bool,
+    pub score: Score,
+    pub enemy_score: Score,
 }
 
 pub struct Status {
@@ -58,18 +65,19 @@ pub struct Status {
 pub enum Response {
     Walk(f32),
     SoundEffect(SoundEffect),
-    NewState(Map),
+    NewState(Box<Map>),
     ThrownItem(ThrownItem),
     Explosion(Explosion),
     Bullet(Bullet),
     Message(String),
+    Noise(Noise),
     GameOver(GameStats),
     InvalidCommand,
 }
 
 impl Response {
     pub fn new_state(map: &mut Map, side: Side) -> Self {
-        Response::NewState(map.clone_visible(side))
+        Response::NewState(Box::new(map.clone_visible(side)))
     }
 
     pub fn new_explosion(
@@ -92,14 +100,8 @@ impl Response {
         Response::ThrownItem(ThrownItem::new(image, start_x, start_y, end_x, end_y))
     }
 
-    pub fn new_bullet(
-        unit: &Unit,
-        target_x: usize,
-        target_y: usize,
-        will_hit: bool,
-        map: &Map,
-    ) -> Self {
-        Response::Bullet(Bullet::new(unit, target_x, target_y, will_hit, map))
+    pub fn new_bullet(unit: &Unit, target_x: f32, target_y: f32) -> Self {
+        Response::Bullet(Bullet::new(unit, target_x, target_y))
     }
 
     pub fn step(
@@ -114,7 +116,7 @@ impl Response {
         match *self {
             Response::NewState(ref new_map) => {
                 let enemies = VisibleEnemies::new(side, map);
-                map.update_from(new_map.clone(), side);
+                map.update_from((**new_map).clone(), side);
 
                 if let Some((new_x, new_y)) = enemies.new_enemy(side, map) {
                     ui.append_to_log("Enemy spotted!");
@@ -147,6 +149,20 @@ impl Response {
                     blocking: false,
                 }
             }
+            Response::Noise(ref noise) => {
+                if map.hear(noise.clone()) {
+                    ui.append_to_log(&format!(
+                        "{} heard {}",
+                        noise.tag,
+                        noise.direction(side, map)
+                    ));
+                }
+
+                Status {
+                    finished: true,
+                    blocking: false,
+                }
+            }
             Response::Explosion(ref mut explosion) => explosion.step(dt),
             Response::ThrownItem(ref mut item) => item.step(dt),
             Response::Bullet(ref mut bullet) => bullet.step(dt),
@@ -182,6 +198,13 @@ impl Response {
         }
     }
 
+    pub fn as_game_over(&self) -> Option<&GameStats> {
+        match *self {
+            Response::GameOver(ref stats) => Some(stats),
+            _ => None,
+        }
+    }
+
     pub fn as_bullet(&self) -> Option<&Bullet> {
         match *self {
             Response::Bullet(ref bullet) if !bullet.finished && bullet.time > 0.0 => Some(bullet),
@@ -242,6 +265,110 @@ impl Explosion {
     }
 }
 
+// The types of noise that can be heard outside of line of sight
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum NoiseType {
+    Footsteps,
+    Gunfire,
+    Explosion,
+}
+
+impl NoiseType {
+    // How far away the noise can be heard
+    pub fn radius(self) -> f32 {
+        match self {
+            NoiseType::Footsteps => 5.0,
+            NoiseType::Gunfire => 20.0,
+            NoiseType::Explosion => 30.0,
+        }
+    }
+}
+
+impl fmt::Display for NoiseType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                NoiseType::Footsteps => "Footsteps",
+                NoiseType::Gunfire => "Gunfire",
+                NoiseType::Explosion => "An explosion",
+            }
+        )
+    }
+}
+
+// A noise with an approximate position
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Noise {
+    pub x: usize,
+    pub y: usize,
+    pub tag: NoiseType,
+    pub turn: u16,
+}
+
+impl Noise {
+    // How many tiles off the position of the noise can be
+    pub const ACCURACY: usize = 2;
+
+    pub fn new(x: usize, y: usize, tag: NoiseType, map: &Map) -> Self {
+        let mut rng = rand::thread_rng();
+        let mut offset = |value: usize, size: usize| {
+            let value = value + Self::ACCURACY;
+            let value = rng.gen_range(value - Self::ACCURACY, value + Self::ACCURACY + 1);
+            clamp(value, Self::ACCURACY, size - 1 + Self::ACCURACY) - Self::ACCURACY
+        };
+
+        let x = offset(x, map.tiles.width());
+        let y = offset(y, map.tiles.height());
+
+        Self {
+            x,
+            y,
+            tag,
+            turn: map.turn(),
+        }
+    }
+
+    // Noises are forgotten after a round
+    pub fn is_recent(&self, turn: u16) -> bool {
+        self.turn + 1 >= turn
+    }
+
+    // Describe the compass direction of the noise (as it appears on the screen) from the closest
+    // unit on a side
+    pub fn direction(&self, side: Side, map: &Map) -> &'static str {
+        const DIRECTIONS: [&str; 8] = [
+            "to the east",
+            "to the north-east",
+            "to the north",
+            "to the north-west",
+            "to the west",
+            "to the south-west",
+            "to the south",
+            "to the south-east",
+        ];
+
+        let closest = map
+            .units
+            .iter()
+            .filter(|unit| unit.side == side)
+            .map(|unit| (unit.x, unit.y))
+            .min_by_key(|&(x, y)| distance(x, y, self.x, self.y) as u32);
+
+        let (unit_x, unit_y) = match closest {
+            Some(position) => position,
+            None => return "in the distance",
+        };
+
+        let (x, y) = from_map_coords(self.x as f32 - unit_x as f32, self.y as f32 - unit_y as f32);
+        let angle = y.atan2(x);
+        let index = (angle / (PI / 4.0)).round() as i32;
+
+        DIRECTIONS[((index + 8) % 8) as usize]
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ThrownItem {
     image: Image,
@@ -304,6 +431,15 @@ impl ThrownItem {
 
 const MARGIN: f32 = 5.0;
 
+// Work out where a bullet that misses ends up, by changing its direction slightly and sending it off the map
+pub fn miss_target(x: usize, y: usize, target_x: usize, target_y: usize, map: &Map) -> (f32, f32) {
+    let (x, y) = (x as f32, y as f32);
+    let direction =
+        direction(x, y, target_x as f32, target_y as f32) + rand::thread_rng().gen_range(-0.2, 0.2);
+
+    extrapolate(x, y, x + direction.cos(), y + direction.sin(), map)
+}
+
 // Extrapolate two points on the map to get the point at which a bullet
 // would go off the map
 fn extrapolate(x_1: f32, y_1: f32, x_2: f32, y_2: f32, map: &Map) -> (f32, f32) {
@@ -358,25 +494,13 @@ impl Bullet {
     // The minimum length of time for a bullet animation is a quarter of a second
     const MIN_TIME: f32 = 0.25;
 
-    // Create a new bullet based of the firing unit and the target unit
-    fn new(unit: &Unit, target_x: usize, target_y: usize, will_hit: bool, map: &Map) -> Self {
+    // Create a new bullet going from the firing unit to where it ends up
+    fn new(unit: &Unit, target_x: f32, target_y: f32) -> Self {
         let x = unit.x as f32;
         let y = unit.y as f32;
-        let mut target_x = target_x as f32;
-        let mut target_y = target_y as f32;
 
         // Calculate the direction of the bullet
-        let mut direction = direction(x, y, target_x, target_y);
-
-        // If the bullet won't hit the target, change the direction slightly
-        if !will_hit {
-            direction += rand::thread_rng().gen_range(-0.2, 0.2);
-
-            let (x, y) = extrapolate(x, y, x + direction.cos(), y + direction.sin(), map);
-
-            target_x = x;
-            target_y = y;
-        }
+        let direction = direction(x, y, target_x, target_y);
 
         Self {
             x,
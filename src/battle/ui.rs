@@ -1,5 +1,6 @@
 use glium::glutin::VirtualKeyCode;
 
+use super::abilities::*;
 use super::map::*;
 use super::networking::*;
 use super::responses::*;
@@ -17,6 +18,7 @@ pub enum ButtonType {
     EndTurn,
     Inventory,
     SaveGame,
+    Ability(Ability),
 }
 
 struct InventoryInfo {
@@ -54,6 +56,7 @@ impl InventoryInfo {
 pub struct Interface {
     game_over: List,
     buttons: [Button; 3],
+    ability_buttons: Vec<(Button, Ability)>,
     save_game: TextInput,
     save_game_active: bool,
     unit_inventory: List,
@@ -61,6 +64,7 @@ pub struct Interface {
     unit_title: TextDisplay,
     tile_title: TextDisplay,
     inventory_active: bool,
+    target: Option<u8>,
     game_info: TextDisplay,
     log: TextDisplay,
 }
@@ -86,6 +90,7 @@ impl Interface {
                     "Save Game",
                 ),
             ],
+            ability_buttons: Vec::new(),
             save_game: TextInput::new(
                 HorizontalAlign::Middle(0.0),
                 VerticalAlign::Middle(0.0),
@@ -103,6 +108,7 @@ impl Interface {
                 VerticalAlign::Middle(INVENTORY_TILE_TITLE_OFFSET),
             ),
             inventory_active: false,
+            target: None,
             game_info: TextDisplay::new(HorizontalAlign::Middle(10.0), VerticalAlign::Top(10.0)),
             log: TextDisplay::new(HorizontalAlign::Left(10.0), VerticalAlign::Bottom(10.0)),
         }
@@ -151,6 +157,29 @@ impl Interface {
         }
     }
 
+    // Cycle through the allies next to a unit that items can be used on or handed to
+    fn cycle_target(&mut self, map: &Map, selected: u8) {
+        let mut targets: Vec<u8> = match map.units.get(selected) {
+            Some(unit) => map
+                .units
+                .iter()
+                .filter(|other| other.side == unit.side && unit.adjacent_to(other))
+                .map(|other| other.id)
+                .collect(),
+            None => Vec::new(),
+        };
+
+        targets.sort();
+
+        self.target = match self
+            .target
+            .and_then(|target| targets.iter().position(|id| *id == target))
+        {
+            Some(index) => targets.get(index + 1).cloned(),
+            None => targets.first().cloned(),
+        };
+    }
+
     pub fn try_handle_inventory_keypress(
         &mut self,
         key: VirtualKeyCode,
@@ -185,11 +214,39 @@ impl Interface {
 
                 //self.inventory.menu_mut(active).fit_selection();
             }
-            // Use an item
+            // Use an item or salvage an item on the ground
             VirtualKeyCode::E => {
+                let index = self.active_inventory().index();
+
                 if self.unit_inventory.is_active() {
-                    let index = self.active_inventory().index();
                     client.use_item(selected, index);
+                } else {
+                    client.salvage_item(selected, index);
+                }
+            }
+            // Pick an ally to use or hand items to
+            VirtualKeyCode::Tab => self.cycle_target(&client.map, selected),
+            // Use an item on the ally
+            VirtualKeyCode::R if self.unit_inventory.is_active() => {
+                if let Some(target) = self.target {
+                    client.use_item_on(selected, self.active_inventory().index(), target);
+                }
+            }
+            // Hand an item to the ally
+            VirtualKeyCode::G if self.unit_inventory.is_active() => {
+                if let Some(target) = self.target {
+                    client.give_item(selected, self.active_inventory().index(), target);
+                }
+            }
+            // Deploy an item onto the tile under the cursor
+            VirtualKeyCode::Q if self.unit_inventory.is_active() => {
+                if let Some((cursor_x, cursor_y)) = cursor {
+                    client.deploy_item(
+                        selected,
+                        self.active_inventory().index(),
+                        *cursor_x,
+                        *cursor_y,
+                    );
                 }
             }
             // Throw an item
@@ -232,7 +289,11 @@ impl Interface {
             Some(0) => Some(ButtonType::EndTurn),
             Some(1) => Some(ButtonType::Inventory),
             Some(2) => Some(ButtonType::SaveGame),
-            _ => None,
+            _ => self
+                .ability_buttons
+                .iter()
+                .find(|(button, _)| button.clicked(ctx))
+                .map(|&(_, ability)| ButtonType::Ability(ability)),
         }
     }
 
@@ -240,9 +301,52 @@ impl Interface {
         // Get a string of info about the selected unit
         let selected = selected_id
             .and_then(|selected| map.units.get(selected))
-            .map(|unit| unit.info())
+            .map(|unit| {
+                let info = if map.objective.is_vip(unit.id) {
+                    format!("VIP {}", unit.info())
+                } else {
+                    unit.info()
+                };
+
+                // List the abilities that are cooling down
+                let cooldowns: Vec<String> = unit
+                    .tag
+                    .abilities()
+                    .iter()
+                    .filter(|ability| unit.cooldown(**ability) > 0)
+                    .map(|ability| format!("{} ({})", ability, unit.cooldown(*ability)))
+                    .collect();
+
+                if cooldowns.is_empty() {
+                    info
+                } else {
+                    format!("{}\nCooling down: {}", info, cooldowns.join(", "))
+                }
+            })
             .unwrap_or_else(String::new);
 
+        // Show a button for each of the selected unit's abilities, above the other buttons
+        self.ability_buttons = selected_id
+            .and_then(|selected| map.units.get(selected))
+            .filter(|unit| unit.side == map.side)
+            .map(|unit| {
+                unit.tag
+                    .abilities()
+                    .iter()
+                    .enumerate()
+                    .map(|(i, ability)| {
+                        let button = Button::new(
+                            HorizontalAlign::Right(i as f32),
+                            VerticalAlign::Bottom(1.0),
+                            ability.name(),
+                        );
+
+                        (button, *ability)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
         let side = if verses_ai {
             map.side.vs_ai_string()
         } else {
@@ -250,8 +354,20 @@ impl Interface {
         };
 
         // Set the text of the UI text display
-        self.game_info
-            .set_text(format!("Turn {} - {}\n{}", map.turn(), side, selected));
+        let turn = match map.turn_limit {
+            _ if map.deployment.is_active() => "Deployment (End Turn to confirm)".into(),
+            Some(limit) => format!("Turn {}/{}", map.turn(), limit),
+            None => format!("Turn {}", map.turn()),
+        };
+
+        self.game_info.set_text(format!(
+            "{} - {}\n{}{}\n{}",
+            turn,
+            side,
+            map.objective.info(),
+            map.reinforcements.info(),
+            selected
+        ));
 
         // Set the inventory
         if self.inventory_active {
@@ -259,10 +375,23 @@ impl Interface {
                 .and_then(|selected| map.units.get(selected))
                 .map(|unit| InventoryInfo::new(unit, map));
 
-            // Get the name of the selected unit, it's items and the items on the ground
+            // Forget the target if it's no longer next to the selected unit
+            let selected = selected_id.and_then(|selected| map.units.get(selected));
+            let target = self.target.and_then(|target| map.units.get(target));
+
+            self.target = match (selected, target) {
+                (Some(selected), Some(target)) if selected.adjacent_to(target) => Some(target.id),
+                _ => None,
+            };
+
+            // Get the name of the selected unit, it's items, the items on the ground and the target
             if let Some(info) = info {
                 self.unit_title.set_text(info.string);
-                self.tile_title.set_text("Ground".into());
+                self.tile_title
+                    .set_text(match target.filter(|_| self.target.is_some()) {
+                        Some(target) => format!("Ground\nTarget: {}", target.name()),
+                        None => "Ground\nTarget: None (Tab)".into(),
+                    });
 
                 self.unit_inventory
                     .set_entries(vec_or_default(info.items, || ListItem::new("No items")));
@@ -275,6 +404,10 @@ impl Interface {
             button.render(ctx);
         }
 
+        for (button, _) in &self.ability_buttons {
+            button.render(ctx);
+        }
+
         if self.save_game_active {
             self.save_game.render(ctx);
         }
@@ -296,14 +429,39 @@ impl Interface {
     }
 
     pub fn set_game_over_screen(&mut self, stats: &GameStats) {
-        self.game_over.set_entries(vec![
+        let draw = !stats.won && stats.score.points() == stats.enemy_score.points();
+
+        let result = if stats.won {
+            "Game Over"
+        } else if stats.turn_limit_reached && draw {
+            "Draw"
+        } else {
+            "You Lost"
+        };
+
+        let mut entries = vec![
             ListItem::new("Game Over").unselectable(),
-            ListItem::new(if stats.won { "Game Over" } else { "You Lost" }).unselectable(),
+            ListItem::new(result).unselectable(),
             ListItem::new(&format!("Units lost: {}", stats.units_lost)).unselectable(),
             ListItem::new(&format!("Units killed: {}", stats.units_killed)).unselectable(),
-            ListItem::new("Close"),
-        ]);
+            ListItem::new(&format!(
+                "Objective: {} - {}",
+                stats.objective, stats.objective_status
+            ))
+            .unselectable(),
+        ];
+
+        if stats.turn_limit_reached {
+            entries.push(ListItem::new("Turn limit reached").unselectable());
+        }
+
+        entries.push(ListItem::new(&format!("Points: {}", stats.score)).unselectable());
+        entries.push(ListItem::new(&format!("Enemy Points: {}", stats.enemy_score)).unselectable());
+        entries.push(ListItem::new("Close"));
+
+        let close = entries.len() - 1;
+        self.game_over.set_entries(entries);
         self.game_over.set_active(true);
-        self.game_over.set_index(4);
+        self.game_over.set_index(close);
     }
 }
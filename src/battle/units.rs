@@ -7,16 +7,26 @@ use std::collections::hash_map::*;
 use std::fmt;
 use std::iter::*;
 
+use super::abilities::Ability;
+use super::loadouts::Loadout;
 use super::map::*;
 use super::paths::PathPoint;
 use context::*;
-use items::Item;
+use items::{burn_fuses, take_items, Item, GRENADE_FUSE};
 use resources::Image;
-use utils::{chance_to_hit, distance_under};
+use utils::distance_under;
 use weapons::{Weapon, WeaponType};
 
 // The cost for a unit to pick up / drop / use / throw an item
 pub const ITEM_COST: u16 = 5;
+// The cost for a unit to salvage an item
+pub const SALVAGE_COST: u16 = 15;
+// The cost for a unit to open or close a door
+pub const DOOR_COST: u16 = 4;
+// The extra cost for a unit to drag a downed ally along with it
+pub const DRAG_COST: u16 = 4;
+// How many of its side's turns a downed unit lasts before bleeding out
+pub const BLEED_OUT_TURNS: u8 = 3;
 
 // A list of first names to pick from
 const FIRST_NAMES: &[&str] = &[
@@ -58,6 +68,11 @@ fn generate_machine_name(rng: &mut ThreadRng) -> String {
     format!("SK{:05}", rng.gen_range(0, 100_000))
 }
 
+// Generate a new random turret name
+fn generate_turret_name(rng: &mut ThreadRng) -> String {
+    format!("TR{:03}", rng.gen_range(0, 1000))
+}
+
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum Side {
     PlayerA,
@@ -201,6 +216,77 @@ impl UnitFacing {
             (cw_turns, true)
         }
     }
+
+    // Get the flank that a unit at a tile facing this way would be shot in from another tile
+    pub fn flank(self, x: usize, y: usize, from_x: usize, from_y: usize) -> Flank {
+        let (turns, _) = self.rotation_cost_and_direction(Self::from_points(x, y, from_x, from_y));
+
+        match turns {
+            0 | 1 => Flank::Front,
+            2 => Flank::Side,
+            _ => Flank::Rear,
+        }
+    }
+}
+
+// Which part of a unit a shot comes from, depending on the way it's facing
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum Flank {
+    Front,
+    Side,
+    Rear,
+}
+
+impl Flank {
+    // How much the flank multiplies the chance to hit
+    pub fn hit_modifier(self) -> f32 {
+        match self {
+            Flank::Front => 1.0,
+            Flank::Side => 1.15,
+            Flank::Rear => 1.3,
+        }
+    }
+
+    // Scale an amount of damage for the flank
+    pub fn damage(self, damage: i16) -> i16 {
+        match self {
+            Flank::Front => damage,
+            Flank::Side => damage * 5 / 4,
+            Flank::Rear => damage * 3 / 2,
+        }
+    }
+
+    // How much the flank multiplies the critical hit bonus against a target out of cover
+    pub fn crit_modifier(self) -> f32 {
+        match self {
+            Flank::Front => 1.0,
+            Flank::Side => 1.5,
+            Flank::Rear => 2.0,
+        }
+    }
+}
+
+impl fmt::Display for Flank {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                Flank::Front => "Front",
+                Flank::Side => "Flanked",
+                Flank::Rear => "Rear",
+            }
+        )
+    }
+}
+
+// The state of a unit that has been taken down but not killed
+#[derive(PartialEq, Copy, Clone, Serialize, Deserialize, Debug)]
+pub enum Downed {
+    // Bleeding out, with a number of turns left
+    Bleeding(u8),
+    // Out of the fight, but in no danger of dying
+    Stabilised,
 }
 
 // The type of a unit
@@ -208,6 +294,7 @@ impl UnitFacing {
 pub enum UnitType {
     Squaddie,
     Machine,
+    Turret,
 }
 
 impl UnitType {
@@ -215,6 +302,7 @@ impl UnitType {
         match self {
             UnitType::Squaddie => 30,
             UnitType::Machine => 25,
+            UnitType::Turret => 10,
         }
     }
 
@@ -222,6 +310,7 @@ impl UnitType {
         match self {
             UnitType::Squaddie => 100,
             UnitType::Machine => 150,
+            UnitType::Turret => 60,
         }
     }
 
@@ -229,13 +318,45 @@ impl UnitType {
         match self {
             UnitType::Squaddie => 25.0,
             UnitType::Machine => 75.0,
+            UnitType::Turret => 0.0,
         }
     }
 
+    // The weapons the unit can be given
+    pub fn weapons(self) -> &'static [WeaponType] {
+        match self {
+            UnitType::Squaddie => &[WeaponType::Rifle, WeaponType::MachineGun],
+            UnitType::Machine => &[WeaponType::PlasmaRifle],
+            UnitType::Turret => &[WeaponType::MachineGun],
+        }
+    }
+
+    // The special abilities the unit can use
+    pub fn abilities(self) -> &'static [Ability] {
+        match self {
+            UnitType::Squaddie => &[Ability::Sprint, Ability::Scan],
+            UnitType::Machine => &[Ability::Scan, Ability::SelfDestruct],
+            UnitType::Turret => &[],
+        }
+    }
+
+    // Units that can't move and don't count towards the size of a side
+    pub fn stationary(self) -> bool {
+        self == UnitType::Turret
+    }
+
     pub fn sight(self) -> f32 {
         Unit::SIGHT
     }
 
+    // Can the unit see in the dark
+    pub fn night_vision(self) -> bool {
+        match self {
+            UnitType::Squaddie => false,
+            UnitType::Machine | UnitType::Turret => true,
+        }
+    }
+
     // How far the unit can throw
     pub fn throw_distance(self) -> f32 {
         self.sight() * 1.5
@@ -245,6 +366,7 @@ impl UnitType {
         match self {
             UnitType::Squaddie => Image::SquaddieFront,
             UnitType::Machine => Image::MachineFront,
+            UnitType::Turret => Image::TurretFront,
         }
     }
 
@@ -252,6 +374,7 @@ impl UnitType {
         match self {
             UnitType::Squaddie => Image::SquaddieLeft,
             UnitType::Machine => Image::MachineFront,
+            UnitType::Turret => Image::TurretFront,
         }
     }
 
@@ -259,6 +382,7 @@ impl UnitType {
         match self {
             UnitType::Squaddie => Image::SquaddieRight,
             UnitType::Machine => Image::MachineFront,
+            UnitType::Turret => Image::TurretFront,
         }
     }
 
@@ -266,6 +390,17 @@ impl UnitType {
         match self {
             UnitType::Squaddie => Image::SquaddieBack,
             UnitType::Machine => Image::MachineBack,
+            UnitType::Turret => Image::TurretBack,
+        }
+    }
+
+    // Get the image for the unit type facing a particular direction
+    pub fn image(self, facing: UnitFacing) -> Image {
+        match facing {
+            UnitFacing::Right | UnitFacing::BottomRight => self.right_image(),
+            UnitFacing::Bottom | UnitFacing::BottomLeft => self.front_image(),
+            UnitFacing::Left | UnitFacing::TopLeft => self.left_image(),
+            _ => self.back_image(),
         }
     }
 }
@@ -278,6 +413,7 @@ impl fmt::Display for UnitType {
             match *self {
                 UnitType::Squaddie => "Squaddie",
                 UnitType::Machine => "Machine",
+                UnitType::Turret => "Turret",
             }
         )
     }
@@ -297,6 +433,8 @@ pub struct Unit {
     health: i16,
     name: String,
     inventory: Vec<Item>,
+    cooldowns: Vec<(Ability, u8)>,
+    downed: Option<Downed>,
 }
 
 impl Unit {
@@ -304,71 +442,70 @@ impl Unit {
     pub const WALK_LATERAL_COST: u16 = 2;
     // The cost for a unit to walk diagonally
     pub const WALK_DIAGONAL_COST: u16 = 3;
+    // The extra cost for a unit to climb up a level
+    pub const CLIMB_COST: u16 = 3;
+    // The extra cost for a unit to step down a level
+    pub const DESCEND_COST: u16 = 1;
+    // The damage a unit takes for each level it falls
+    pub const FALL_DAMAGE: i16 = 20;
     // How far a unit can see
     pub const SIGHT: f32 = 7.5;
 
-    // Create a new unit based on unit type
+    // Create a new unit based on unit type, with the standard loadout
     pub fn new(tag: UnitType, side: Side, x: usize, y: usize, facing: UnitFacing, id: u8) -> Unit {
         let mut rng = rand::thread_rng();
-
-        match tag {
-            UnitType::Squaddie => {
-                // Randomly choose a weapon
-                let weapon_type = if rng.gen() {
-                    WeaponType::Rifle
-                } else {
-                    WeaponType::MachineGun
-                };
-                let capacity = weapon_type.capacity();
-
-                Unit {
-                    tag,
-                    side,
-                    x,
-                    y,
-                    facing,
-                    id,
-                    weapon: Weapon::new(weapon_type, capacity),
-                    name: generate_squaddie_name(&mut rng),
-                    moves: tag.moves(),
-                    health: tag.health(),
-                    inventory: if let WeaponType::Rifle = weapon_type {
-                        vec![
-                            Item::RifleClip(capacity),
-                            Item::RifleClip(capacity),
-                            Item::Bandages,
-                            Item::Grenade(false),
-                        ]
-                    } else {
-                        vec![
-                            Item::MachineGunClip(capacity),
-                            Item::MachineGunClip(capacity),
-                            Item::Bandages,
-                            Item::Grenade(false),
-                        ]
-                    },
-                }
-            }
-            UnitType::Machine => Unit {
-                tag,
-                side,
-                x,
-                y,
-                facing,
-                id,
-                weapon: Weapon::new(WeaponType::PlasmaRifle, WeaponType::PlasmaRifle.capacity()),
-                name: generate_machine_name(&mut rng),
-                moves: tag.moves(),
-                health: tag.health(),
-                inventory: Vec::new(),
+        let loadout = Loadout::new(tag);
+
+        Unit {
+            tag,
+            side,
+            x,
+            y,
+            facing,
+            id,
+            weapon: Weapon::new(loadout.weapon, loadout.weapon.capacity()),
+            name: match tag {
+                UnitType::Squaddie => generate_squaddie_name(&mut rng),
+                UnitType::Machine => generate_machine_name(&mut rng),
+                UnitType::Turret => generate_turret_name(&mut rng),
             },
+            moves: tag.moves(),
+            health: tag.health(),
+            inventory: loadout.items(),
+            cooldowns: Vec::new(),
+            downed: None,
         }
     }
 
+    // Swap the unit's weapon and items for a loadout
+    pub fn equip(&mut self, loadout: &Loadout) {
+        self.weapon = Weapon::new(loadout.weapon, loadout.weapon.capacity());
+        self.inventory = loadout.items();
+    }
+
     pub fn inventory(&self) -> &[Item] {
         &self.inventory
     }
 
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    // Is another unit on one of the tiles around this one
+    pub fn adjacent_to(&self, other: &Unit) -> bool {
+        self.id != other.id && distance_under(self.x, self.y, other.x, other.y, 1.5)
+    }
+
+    // Could the unit carry an item without going over capacity
+    pub fn can_carry(&self, item: Item) -> bool {
+        self.carrying() + item.weight() <= self.tag.capacity()
+    }
+
+    // Add an item to the inventory
+    pub fn add_item(&mut self, item: Item) {
+        self.inventory.push(item);
+    }
+
     // The weight of the items that the units is carrying
     pub fn carrying(&self) -> f32 {
         self.inventory
@@ -378,9 +515,57 @@ impl Unit {
             })
     }
 
+    // How much the unit's load slows it down, from nothing at half its capacity to doubling walk costs at full capacity
+    pub fn encumbrance(&self) -> f32 {
+        let capacity = self.tag.capacity();
+
+        if capacity > 0.0 && self.carrying() > capacity / 2.0 {
+            ((self.carrying() / capacity - 0.5) * 2.0).min(1.0)
+        } else {
+            0.0
+        }
+    }
+
+    // Damage the unit and get whether it was killed.
+    // Squaddies go down and start bleeding out instead, unless they're already down
     pub fn damage(&mut self, damage: i16) -> bool {
         self.health -= damage;
-        self.health <= 0
+
+        if self.health > 0 {
+            false
+        } else if self.tag == UnitType::Squaddie && self.downed.is_none() {
+            self.health = 0;
+            self.moves = 0;
+            self.downed = Some(Downed::Bleeding(BLEED_OUT_TURNS));
+            false
+        } else {
+            true
+        }
+    }
+
+    pub fn is_downed(&self) -> bool {
+        self.downed.is_some()
+    }
+
+    // Give the unit its moves back, unless it's down
+    pub fn refill_moves(&mut self) {
+        self.moves = if self.is_downed() {
+            0
+        } else {
+            self.tag.moves()
+        };
+    }
+
+    // Lose a turn to bleeding and get whether the unit has bled out
+    pub fn bleed(&mut self) -> bool {
+        match self.downed {
+            Some(Downed::Bleeding(turns)) if turns > 1 => {
+                self.downed = Some(Downed::Bleeding(turns - 1));
+                false
+            }
+            Some(Downed::Bleeding(_)) => true,
+            _ => false,
+        }
     }
 
     // Move the unit to a location with a specific cost
@@ -400,14 +585,53 @@ impl Unit {
         }
     }
 
-    // Get the chance-to-hit of a tile from the unit
-    pub fn chance_to_hit(&self, target_x: usize, target_y: usize) -> f32 {
-        chance_to_hit(self.x, self.y, target_x, target_y)
+    // Get the chance-to-hit of a tile from the unit, with a flank on whatever is there
+    pub fn chance_to_hit(
+        &self,
+        tiles: &Tiles,
+        target_x: usize,
+        target_y: usize,
+        flank: Flank,
+    ) -> f32 {
+        (tiles.chance_to_hit(self.x, self.y, target_x, target_y) * flank.hit_modifier()).min(1.0)
+    }
+
+    // Get the flank the unit would be shooting a tile's unit in (if there is one)
+    pub fn flank(&self, units: &Units, target_x: usize, target_y: usize) -> Flank {
+        units
+            .at(target_x, target_y)
+            .map(|target| target.facing.flank(target.x, target.y, self.x, self.y))
+            .unwrap_or(Flank::Front)
+    }
+
+    // Get the chance of a hit on a tile from the unit being critical, with a flank on whatever is there
+    pub fn crit_chance(
+        &self,
+        tiles: &Tiles,
+        target_x: usize,
+        target_y: usize,
+        flank: Flank,
+    ) -> f32 {
+        tiles.crit_chance(self.x, self.y, target_x, target_y, flank)
     }
 
     pub fn can_heal_from(&self, item: Item) -> bool {
+        self.moves >= ITEM_COST && self.needs_healing_from(item)
+    }
+
+    // Would the unit be healed by an item without it going to waste
+    pub fn needs_healing_from(&self, item: Item) -> bool {
         let amount = item.heal(self.tag);
-        amount > 0 && self.moves >= ITEM_COST && self.tag.health() - self.health >= amount
+        amount > 0 && self.tag.health() - self.health >= amount
+    }
+
+    // Heal the unit with an item from another unit, stabilising it if it's bleeding out
+    pub fn heal_from(&mut self, item: Item) {
+        self.health += item.heal(self.tag);
+
+        if let Some(Downed::Bleeding(_)) = self.downed {
+            self.downed = Some(Downed::Stabilised);
+        }
     }
 
     pub fn can_reload_from(&self, item: Item) -> bool {
@@ -430,7 +654,7 @@ impl Unit {
         let tile = tiles.at_mut(self.x, self.y);
 
         if let Some(item) = tile.items_remove(index) {
-            if self.carrying() + item.weight() <= self.tag.capacity() {
+            if self.can_carry(item) {
                 self.inventory.push(item);
                 self.moves -= ITEM_COST;
                 return;
@@ -438,6 +662,21 @@ impl Unit {
         }
     }
 
+    // Break an item on the unit's tile down into parts
+    pub fn salvage_item(&mut self, tiles: &mut Tiles, index: usize) {
+        if self.moves < SALVAGE_COST {
+            return;
+        }
+
+        let tile = tiles.at_mut(self.x, self.y);
+
+        if let Some(mut parts) = tile.items.get(index).and_then(|item| item.salvage()) {
+            tile.items.remove(index);
+            tile.items.append(&mut parts);
+            self.moves -= SALVAGE_COST;
+        }
+    }
+
     pub fn use_item(&mut self, index: usize) {
         let mut item_consumed = false;
         let mut new_item = None;
@@ -470,12 +709,12 @@ impl Unit {
                     true
                 }
                 // Use other items
-                (Item::Bandages, _) if self.can_heal_from(*item) => {
+                (Item::Bandages, _) | (Item::Scrap, _) if self.can_heal_from(*item) => {
                     self.health += item.heal(self.tag);
                     true
                 }
-                (Item::Grenade(primed), _) if !primed => {
-                    new_item = Some(Item::Grenade(true));
+                (Item::Grenade(None), _) => {
+                    new_item = Some(Item::Grenade(Some(GRENADE_FUSE)));
                     true
                 }
                 _ => false,
@@ -497,6 +736,49 @@ impl Unit {
         }
     }
 
+    // Take all the items in the inventory that match a predicate
+    pub fn take_items<P: Fn(&Item) -> bool>(&mut self, predicate: P) -> Vec<Item> {
+        take_items(&mut self.inventory, predicate)
+    }
+
+    // Burn down the fuses of any primed grenades in the inventory
+    pub fn burn_fuses(&mut self) -> bool {
+        burn_fuses(&mut self.inventory)
+    }
+
+    // How many turns until the unit can use an ability again
+    pub fn cooldown(&self, ability: Ability) -> u8 {
+        self.cooldowns
+            .iter()
+            .find(|&&(other, _)| other == ability)
+            .map(|&(_, turns)| turns)
+            .unwrap_or(0)
+    }
+
+    pub fn can_use_ability(&self, ability: Ability) -> bool {
+        self.tag.abilities().contains(&ability)
+            && self.cooldown(ability) == 0
+            && self.moves >= ability.cost()
+    }
+
+    // Spend the moves needed for an ability and start its cooldown
+    pub fn use_ability(&mut self, ability: Ability) {
+        self.moves -= ability.cost();
+
+        if ability.cooldown() > 0 {
+            self.cooldowns.push((ability, ability.cooldown()));
+        }
+    }
+
+    // Count down the ability cooldowns at the end of a turn
+    pub fn cool_down(&mut self) {
+        for &mut (_, ref mut turns) in &mut self.cooldowns {
+            *turns -= 1;
+        }
+
+        self.cooldowns.retain(|&(_, turns)| turns > 0);
+    }
+
     pub fn fire_weapon(&mut self) -> bool {
         let can_fire = self.moves >= self.weapon.tag.cost() && self.weapon.can_fire();
 
@@ -510,31 +792,38 @@ impl Unit {
 
     pub fn info(&self) -> String {
         format!(
-            "Name: {}, Moves: {}, Health: {}, Weapon: {}",
-            self.name, self.moves, self.health, self.weapon
+            "Name: {}, Moves: {}, Health: {}, Weapon: {}{}",
+            self.name,
+            self.moves,
+            self.health,
+            self.weapon,
+            match self.downed {
+                Some(Downed::Bleeding(turns)) =>
+                    format!(" (Down, bleeding out in {} turns)", turns),
+                Some(Downed::Stabilised) => " (Down, stabilised)".into(),
+                None => String::new(),
+            }
         )
     }
 
     pub fn carrying_info(&self) -> String {
         format!(
-            "{}\n{} - {} kg\nCarry Capacity: {}/{} kg",
+            "{}\n{} - {} kg\nCarry Capacity: {}/{} kg{}",
             self.name,
             self.weapon,
             self.weapon.tag.weight(),
             self.carrying(),
-            self.tag.capacity()
+            self.tag.capacity(),
+            if self.encumbrance() > 0.0 {
+                " (Encumbered)"
+            } else {
+                ""
+            }
         )
     }
 
     pub fn render(&self, ctx: &mut Context, dest: [f32; 2], zoom: f32, overlay: [f32; 4]) {
-        let image = match self.facing {
-            UnitFacing::Right | UnitFacing::BottomRight => self.tag.right_image(),
-            UnitFacing::Bottom | UnitFacing::BottomLeft => self.tag.front_image(),
-            UnitFacing::Left | UnitFacing::TopLeft => self.tag.left_image(),
-            _ => self.tag.back_image(),
-        };
-
-        ctx.render_with_overlay(image, dest, zoom, overlay);
+        ctx.render_with_overlay(self.tag.image(self.facing), dest, zoom, overlay);
     }
 }
 
@@ -561,6 +850,7 @@ impl Units {
     // Add a unit to the struct
     pub fn add(&mut self, tag: UnitType, side: Side, x: usize, y: usize, facing: UnitFacing) {
         match side {
+            _ if tag.stationary() => {}
             Side::PlayerA => self.max_player_a_units += 1,
             Side::PlayerB => self.max_player_b_units += 1,
         }
@@ -570,6 +860,24 @@ impl Units {
         self.index += 1;
     }
 
+    // Add a unit with a particular loadout
+    pub fn add_with_loadout(
+        &mut self,
+        tag: UnitType,
+        side: Side,
+        x: usize,
+        y: usize,
+        facing: UnitFacing,
+        loadout: &Loadout,
+    ) {
+        let id = self.index;
+        self.add(tag, side, x, y, facing);
+
+        if let Some(unit) = self.get_mut(id) {
+            unit.equip(loadout);
+        }
+    }
+
     // Iterate over the units
     pub fn iter(&self) -> Values<u8, Unit> {
         self.units.values()
@@ -600,9 +908,18 @@ impl Units {
         self.iter_mut().find(|unit| unit.x == x && unit.y == y)
     }
 
-    // Count the number of units on a particular side
+    // Count the number of (non-stationary) units on a particular side
     pub fn count(&self, side: Side) -> u8 {
-        self.iter().filter(|unit| unit.side == side).count() as u8
+        self.iter()
+            .filter(|unit| unit.side == side && !unit.tag.stationary())
+            .count() as u8
+    }
+
+    // Count the units on a side that are still able to fight
+    pub fn count_standing(&self, side: Side) -> u8 {
+        self.iter()
+            .filter(|unit| unit.side == side && !unit.tag.stationary() && !unit.is_downed())
+            .count() as u8
     }
 
     // Is a unit on a particular side at (x, y)?
@@ -610,12 +927,20 @@ impl Units {
         self.at(x, y).map(|unit| unit.side == side).unwrap_or(false)
     }
 
+    // Remove a unit from the map along with everything it is carrying
+    pub fn remove(&mut self, tiles: &mut Tiles, id: u8) {
+        if self.units.remove(&id).is_some() {
+            tiles.update_visibility(self);
+        }
+    }
+
     // Kill a unit and drop a corpse
     pub fn kill(&mut self, tiles: &mut Tiles, id: u8) {
         if let Some(unit) = self.get_mut(id) {
             let corpse = match unit.tag {
                 UnitType::Squaddie => Item::SquaddieCorpse,
                 UnitType::Machine => Item::MachineCorpse,
+                UnitType::Turret => Item::Scrap,
             };
 
             // Drop the unit's items
@@ -642,6 +967,7 @@ impl FromIterator<Unit> for Units {
         let units = iterator
             .into_iter()
             .inspect(|unit| match unit.side {
+                _ if unit.tag.stationary() => {}
                 Side::PlayerA => max_player_a_units += 1,
                 Side::PlayerB => max_player_b_units += 1,
             })
@@ -742,3 +1068,25 @@ fn test_turning() {
     let (cost, direction) = UnitFacing::BottomRight.rotation_cost_and_direction(UnitFacing::Top);
     assert_eq!((cost, direction), (3, false));
 }
+
+#[test]
+fn machine_repair() {
+    let mut tiles = Tiles::new(30, 30);
+    let mut units = Units::new();
+    units.add(UnitType::Machine, Side::PlayerA, 0, 0, UnitFacing::Bottom);
+
+    // Salvage a corpse into scrap and repair with it
+
+    tiles.drop(0, 0, Item::MachineCorpse);
+
+    let unit = units.get_mut(0).unwrap();
+    unit.salvage_item(&mut tiles, 0);
+    assert_eq!(unit.moves, UnitType::Machine.moves() - SALVAGE_COST);
+    assert!(tiles.at(0, 0).items.contains(&Item::Scrap));
+
+    unit.pickup_item(&mut tiles, 0);
+    unit.damage(50);
+    unit.use_item(0);
+    assert!(unit.inventory().is_empty());
+    assert_eq!(unit.health, UnitType::Machine.health() - 25);
+}
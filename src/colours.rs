@@ -7,3 +7,5 @@ pub const YELLOW: [f32; 4] = [0.7529, 0.4287, 0.0395, 1.0];
 pub const ORANGE: [f32; 4] = [0.7529, 0.1779, 0.0, 1.0];
 pub const RED: [f32; 4] = [0.8963, 0.0369, 0.0369, 1.0];
 pub const GREY: [f32; 4] = [0.25, 0.25, 0.25, 1.0];
+pub const GREEN: [f32; 4] = [0.0393, 0.5029, 0.0908, 1.0];
+pub const BLUE: [f32; 4] = [0.0513, 0.2705, 0.7991, 1.0];
@@ -86,6 +86,7 @@ impl Context {
             scale,
             rotation: 0.0,
             overlay_colour: colours::ALPHA,
+            opacity: 1.0,
         });
     }
 
@@ -103,6 +104,26 @@ impl Context {
             scale,
             overlay_colour,
             rotation: 0.0,
+            opacity: 1.0,
+        });
+    }
+
+    // Render a translucent image with a colour overlay
+    pub fn render_translucent(
+        &mut self,
+        image: Image,
+        dest: [f32; 2],
+        scale: f32,
+        overlay_colour: [f32; 4],
+        opacity: f32,
+    ) {
+        self.renderer.render(Properties {
+            src: image.source(),
+            dest,
+            scale,
+            overlay_colour,
+            rotation: 0.0,
+            opacity,
         });
     }
 
@@ -120,6 +141,7 @@ impl Context {
             scale,
             rotation,
             overlay_colour: colours::ALPHA,
+            opacity: 1.0,
         });
     }
 
@@ -64,6 +64,7 @@ pub struct Properties {
     pub dest: [f32; 2],
     pub rotation: f32,
     pub scale: f32,
+    pub opacity: f32,
 }
 
 struct Uniforms {
@@ -149,6 +150,7 @@ impl Renderer {
             prop_overlay_colour: properties.overlay_colour,
             prop_rotation: properties.rotation,
             prop_scale: properties.scale,
+            prop_opacity: properties.opacity,
             sampler: sampler
         };
 
@@ -9,6 +9,7 @@ out vec4 target;
     vec2 prop_dest;
     float prop_rotation;
     float prop_scale;
+    float prop_opacity;
 };*/
 
 uniform vec4 prop_src;
@@ -16,6 +17,7 @@ uniform vec4 prop_overlay_colour;
 uniform vec2 prop_dest;
 uniform float prop_rotation;
 uniform float prop_scale;
+uniform float prop_opacity;
 uniform vec2 tileset_size;
 uniform vec2 screen_resolution;
 uniform sampler2D sampler;
@@ -27,6 +29,6 @@ void main() {
     // Mix it with the overlay colour
     vec3 mixed_colour = mix(colour.rgb, prop_overlay_colour.rgb, prop_overlay_colour.a);
 
-    // Return the mixed colour (with the alpha unchanged)
-    target = vec4(mixed_colour, colour.a);
+    // Return the mixed colour (with the alpha scaled by the opacity)
+    target = vec4(mixed_colour, colour.a * prop_opacity);
 }
\ No newline at end of file
@@ -10,6 +10,7 @@ out vec2 out_uv;
     vec2 prop_dest;
     float prop_rotation;
     float prop_scale;
+    float prop_opacity;
 };*/
 
 uniform vec4 prop_src;
@@ -2,10 +2,14 @@
 
 use std::fmt;
 
+use battle::map::LightSource;
 use battle::units::UnitType;
 use resources::Image;
 use weapons::WeaponType;
 
+// How many turns a primed grenade takes to go off if it isn't thrown
+pub const GRENADE_FUSE: u8 = 2;
+
 // The type of an item
 #[derive(Copy, Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub enum Item {
@@ -17,9 +21,14 @@ pub enum Item {
     RifleClip(u8),
     MachineGunClip(u8),
     PlasmaClip(u8),
-    Grenade(bool),
+    Grenade(Option<u8>),
+    Flare,
+    SmokeGrenade,
+    IncendiaryGrenade,
+    Turret,
     SquaddieCorpse,
     MachineCorpse,
+    Intel,
 }
 
 impl fmt::Display for Item {
@@ -38,11 +47,15 @@ impl fmt::Display for Item {
                     format!("Machine Gun Clip ({}/{})", ammo, self.capacity())
                 }
                 Item::PlasmaClip(ammo) => format!("Plasma Clip ({}/{})", ammo, self.capacity()),
-                Item::Grenade(primed) => {
-                    format!("Grenade ({})", if primed { "Primed" } else { "Not primed" })
-                }
+                Item::Grenade(Some(fuse)) => format!("Grenade (Primed - {} turns)", fuse),
+                Item::Grenade(None) => "Grenade (Not primed)".into(),
+                Item::Flare => "Flare".into(),
+                Item::SmokeGrenade => "Smoke Grenade".into(),
+                Item::IncendiaryGrenade => "Incendiary Grenade".into(),
+                Item::Turret => "Turret".into(),
                 Item::SquaddieCorpse => "Squaddie Corpse".into(),
                 Item::MachineCorpse => "Machine Corpse".into(),
+                Item::Intel => "Intel".into(),
             },
             self.weight()
         )
@@ -58,6 +71,7 @@ impl Item {
             Item::Rifle(_) => 4.0,
             Item::MachineGun(_) => 6.0,
             Item::PlasmaRifle(_) => 5.5,
+            Item::Turret => 10.0,
             Item::SquaddieCorpse => 60.0,
             Item::MachineCorpse => 150.0,
             _ => 0.5,
@@ -70,8 +84,13 @@ impl Item {
             Item::Scrap => Image::Scrap,
             Item::Bandages => Image::Bandages,
             Item::Grenade(_) => Image::Grenade,
+            Item::Flare => Image::Flare,
+            Item::SmokeGrenade => Image::SmokeGrenade,
+            Item::IncendiaryGrenade => Image::IncendiaryGrenade,
+            Item::Turret => Image::Turret,
             Item::SquaddieCorpse => Image::SquaddieCorpse,
             Item::MachineCorpse => Image::MachineCorpse,
+            Item::Intel => Image::Intel,
             Item::RifleClip(_) | Item::MachineGunClip(_) | Item::PlasmaClip(_) => Image::AmmoClip,
             _ => Image::Weapon,
         }
@@ -90,11 +109,26 @@ impl Item {
     // If the item is a healing item, the ammount it heals by
     pub fn heal(self, tag: UnitType) -> i16 {
         match (self, tag) {
-            (Item::Bandages, UnitType::Squaddie) => 25,
+            (Item::Bandages, UnitType::Squaddie) | (Item::Scrap, UnitType::Machine) => 25,
             _ => 0,
         }
     }
 
+    // The items that the item can be broken down into, if any
+    pub fn salvage(self) -> Option<Vec<Item>> {
+        match self {
+            Item::MachineCorpse => {
+                let capacity = WeaponType::PlasmaRifle.capacity();
+                Some(vec![
+                    Item::Scrap,
+                    Item::Scrap,
+                    Item::PlasmaClip(capacity / 2),
+                ])
+            }
+            _ => None,
+        }
+    }
+
     // How much ammo this clip could reload a weapon by
     pub fn ammo(self, tag: WeaponType) -> u8 {
         match (self, tag) {
@@ -105,11 +139,77 @@ impl Item {
         }
     }
 
+    // Does the item light up the area when thrown?
+    pub fn as_light_source(self) -> Option<LightSource> {
+        match self {
+            Item::Flare => Some(LightSource::Flare(LightSource::FLARE_TURNS)),
+            _ => None,
+        }
+    }
+
+    // The type of unit the item becomes when deployed, if any
+    pub fn deploys(self) -> Option<UnitType> {
+        match self {
+            Item::Turret => Some(UnitType::Turret),
+            _ => None,
+        }
+    }
+
+    // The radius of the smoke cloud that the item leaves when thrown, if any
+    pub fn as_smoke(self) -> Option<f32> {
+        match self {
+            Item::SmokeGrenade => Some(2.5),
+            _ => None,
+        }
+    }
+
+    // The radius of the area that the item sets on fire when thrown, if any
+    pub fn as_incendiary(self) -> Option<f32> {
+        match self {
+            Item::IncendiaryGrenade => Some(1.5),
+            _ => None,
+        }
+    }
+
+    // Does the item burn up in a fire?
+    pub fn flammable(self) -> bool {
+        [Item::Bandages, Item::SquaddieCorpse].contains(&self)
+    }
+
+    // Burn down the fuse of a primed grenade, returning whether it has run out
+    pub fn burn_fuse(&mut self) -> bool {
+        match *self {
+            Item::Grenade(Some(ref mut fuse)) => {
+                *fuse = fuse.saturating_sub(1);
+                *fuse == 0
+            }
+            _ => false,
+        }
+    }
+
     // Could the item explode when thrown/dropped?
     pub fn as_explosive(self) -> Option<(i16, f32)> {
         match self {
-            Item::Grenade(primed) if primed => Some((75, 2.5)),
+            Item::Grenade(Some(_)) => Some((75, 2.5)),
             _ => None,
         }
     }
 }
+
+// Take all the items in a list that match a predicate
+pub fn take_items<P: Fn(&Item) -> bool>(items: &mut Vec<Item>, predicate: P) -> Vec<Item> {
+    let (taken, kept) = items.drain(..).partition(|item| predicate(item));
+    *items = kept;
+    taken
+}
+
+// Burn down the fuses of the primed grenades in a list, returning whether any have run out
+pub fn burn_fuses(items: &mut [Item]) -> bool {
+    let mut run_out = false;
+
+    for item in items {
+        run_out |= item.burn_fuse();
+    }
+
+    run_out
+}
@@ -1,5 +1,7 @@
 // The main menu of the game
 
+use battle::loadouts::Kit;
+use battle::units::Side;
 use context::Context;
 use resources::Image;
 use settings::*;
@@ -27,6 +29,7 @@ enum Submenu {
     SkirmishSettings,
     SkirmishSaves,
     Settings,
+    Loadouts,
 }
 
 impl Submenu {
@@ -37,6 +40,7 @@ impl Submenu {
             Submenu::SkirmishSettings => 2,
             Submenu::Settings => 3,
             Submenu::SkirmishSaves => 4,
+            Submenu::Loadouts => 5,
         }
     }
 }
@@ -52,7 +56,10 @@ pub enum MenuCallback<'a> {
 pub struct MainMenu {
     settings: SkirmishSettings,
     submenu: Submenu,
-    submenus: [List; 5],
+    submenus: [List; 6],
+    // The side and unit whose loadout is being edited
+    loadout_side: Side,
+    loadout_unit: usize,
 }
 
 impl MainMenu {
@@ -89,7 +96,15 @@ impl MainMenu {
                     ListItem::new("<Player B Units>"),
                     ListItem::new("<Player A Unit Type>"),
                     ListItem::new("<Player A Unit Type>"),
-                    ListItem::new("<Light Level>")
+                    ListItem::new("<Light Level>"),
+                    ListItem::new("<Weather>"),
+                    ListItem::new("<Objective>"),
+                    ListItem::new("<Turn Limit>"),
+                    ListItem::new("<Player A Reinforcements>"),
+                    ListItem::new("<Player B Reinforcements>"),
+                    ListItem::new("<Reinforcement Turn>"),
+                    ListItem::new("<Reinforcement Arrival>"),
+                    ListItem::new("Loadouts")
                 ),
                 list!(
                     0.0,
@@ -99,12 +114,33 @@ impl MainMenu {
                     ListItem::new("Reset")
                 ),
                 List::new(0.0, 50.0, Vec::new()),
+                list!(
+                    0.0,
+                    50.0,
+                    ListItem::new("Back"),
+                    ListItem::new("<Side>"),
+                    ListItem::new("<Unit>"),
+                    ListItem::new("<Weapon>"),
+                    ListItem::new("<Clips>"),
+                    ListItem::new("<Bandages>"),
+                    ListItem::new("<Grenades>"),
+                    ListItem::new("<Smoke Grenades>"),
+                    ListItem::new("<Incendiary Grenades>"),
+                    ListItem::new("<Flares>"),
+                    ListItem::new("<Turrets>"),
+                    ListItem::new("<Point Budget>"),
+                    ListItem::new("<Weight>").unselectable(),
+                    ListItem::new("<Points>").unselectable()
+                ),
             ],
             settings: SkirmishSettings::default(),
+            loadout_side: Side::PlayerA,
+            loadout_unit: 0,
         };
 
         menu.refresh_skirmish(false);
         menu.refresh_skirmish_settings();
+        menu.refresh_loadouts();
         menu.refresh_settings(ctx);
         menu.refresh_skirmish_saves(ctx);
 
@@ -135,6 +171,63 @@ impl MainMenu {
             "Light Level: {}",
             f32::from(self.settings.light) / 10.0
         ));
+        skirmish_settings[8].set_text(&format!("Weather: {}", self.settings.weather));
+        skirmish_settings[9].set_text(&format!("Objective: {}", self.settings.objective));
+        skirmish_settings[10].set_text(&match self.settings.turn_limit {
+            Some(limit) => format!("Turn Limit: {}", limit),
+            None => "Turn Limit: None".into(),
+        });
+        skirmish_settings[11].set_text(&format!(
+            "Player A Reinforcements: {}",
+            self.settings.player_a_reinforcements
+        ));
+        skirmish_settings[12].set_text(&format!(
+            "Player B Reinforcements: {}",
+            self.settings.player_b_reinforcements
+        ));
+        skirmish_settings[13].set_text(&format!(
+            "Reinforcement Turn: {}",
+            self.settings.reinforcement_turn
+        ));
+        skirmish_settings[14].set_text(&format!(
+            "Reinforcement Arrival: {}",
+            self.settings.reinforcement_arrival
+        ));
+    }
+
+    fn refresh_loadouts(&mut self) {
+        self.settings.clamp();
+
+        let budget = self.settings.loadout_budget;
+        let (loadouts, tag) = self.settings.loadouts_mut(self.loadout_side);
+        self.loadout_unit = self.loadout_unit.min(loadouts.len() - 1);
+        let unit_count = loadouts.len();
+        let loadout = &loadouts[self.loadout_unit];
+
+        let submenu = &mut self.submenus[Submenu::Loadouts.index()];
+        submenu[1].set_text(&format!("Side: {}", self.loadout_side));
+        submenu[2].set_text(&format!(
+            "Unit: {}/{} ({})",
+            self.loadout_unit + 1,
+            unit_count,
+            tag
+        ));
+        submenu[3].set_text(&format!("Weapon: {}", loadout.weapon));
+
+        for (i, kit) in Kit::ALL.iter().enumerate() {
+            submenu[4 + i].set_text(&format!("{}: {}", kit, loadout.count(*kit)));
+        }
+
+        submenu[11].set_text(&match budget {
+            Some(budget) => format!("Point Budget: {}", budget),
+            None => "Point Budget: None".into(),
+        });
+        submenu[12].set_text(&format!(
+            "Weight: {}/{} kg",
+            loadout.weight(),
+            tag.capacity()
+        ));
+        submenu[13].set_text(&format!("Points: {}", loadout.points()));
     }
 
     fn refresh_skirmish(&mut self, game_in_progress: bool) {
@@ -202,6 +295,7 @@ impl MainMenu {
             },
             Submenu::Skirmish => return self.update_skirmish(ctx, game_in_progress),
             Submenu::SkirmishSettings => self.update_skirmish_settings(ctx),
+            Submenu::Loadouts => self.update_loadouts(ctx),
             Submenu::Settings => {
                 match index {
                     0 if enter_pressed => self.submenu = Submenu::Main,
@@ -297,6 +391,36 @@ impl MainMenu {
                 self.settings.light = self.settings.light.saturating_sub(LIGHT_LEVEL_CHANGE)
             }
             7 if movement_right => self.settings.light += LIGHT_LEVEL_CHANGE,
+
+            8 if movement_left => self.settings.weather.rotate_left(),
+            8 if movement_right => self.settings.weather.rotate_right(),
+
+            9 if movement_left => self.settings.objective.rotate_left(),
+            9 if movement_right => self.settings.objective.rotate_right(),
+
+            10 if movement_left => self.settings.decrease_turn_limit(),
+            10 if movement_right => self.settings.increase_turn_limit(),
+
+            11 if movement_left => {
+                self.settings.player_a_reinforcements =
+                    self.settings.player_a_reinforcements.saturating_sub(1)
+            }
+            11 if movement_right => self.settings.player_a_reinforcements += 1,
+            12 if movement_left => {
+                self.settings.player_b_reinforcements =
+                    self.settings.player_b_reinforcements.saturating_sub(1)
+            }
+            12 if movement_right => self.settings.player_b_reinforcements += 1,
+
+            13 if movement_left => self.settings.reinforcement_turn -= 1,
+            13 if movement_right => self.settings.reinforcement_turn += 1,
+
+            14 if movement_left || movement_right => self.settings.reinforcement_arrival.toggle(),
+
+            15 if enter_pressed => {
+                self.refresh_loadouts();
+                self.submenu = Submenu::Loadouts;
+            }
             _ => {}
         }
 
@@ -305,6 +429,50 @@ impl MainMenu {
         }
     }
 
+    pub fn update_loadouts(&mut self, ctx: &Context) {
+        let enter_pressed = ctx.gui.key_pressed(VirtualKeyCode::Return);
+        let movement_left = ctx.gui.key_pressed(VirtualKeyCode::Left);
+        let movement_right = ctx.gui.key_pressed(VirtualKeyCode::Right);
+        let index = self.submenus[self.submenu.index()].index();
+
+        let budget = self.settings.loadout_budget;
+        let unit = self.loadout_unit;
+
+        match index {
+            0 if enter_pressed => self.submenu = Submenu::SkirmishSettings,
+
+            1 if movement_left || movement_right => {
+                self.loadout_side = self.loadout_side.enemies();
+                self.loadout_unit = 0;
+            }
+
+            2 if movement_left => self.loadout_unit = self.loadout_unit.saturating_sub(1),
+            2 if movement_right => self.loadout_unit += 1,
+
+            3 if movement_left || movement_right => {
+                let (loadouts, tag) = self.settings.loadouts_mut(self.loadout_side);
+                loadouts[unit].change_weapon(tag, budget);
+            }
+
+            4..=10 if movement_left => {
+                let (loadouts, _) = self.settings.loadouts_mut(self.loadout_side);
+                loadouts[unit].remove(Kit::ALL[index - 4]);
+            }
+            4..=10 if movement_right => {
+                let (loadouts, tag) = self.settings.loadouts_mut(self.loadout_side);
+                loadouts[unit].add(Kit::ALL[index - 4], tag, budget);
+            }
+
+            11 if movement_left => self.settings.decrease_loadout_budget(),
+            11 if movement_right => self.settings.increase_loadout_budget(),
+            _ => {}
+        }
+
+        if movement_left || movement_right {
+            self.refresh_loadouts();
+        }
+    }
+
     pub fn render(&self, ctx: &mut Context) {
         // Draw the title
         let dest = [ctx.width / 2.0, TITLE_TOP_OFFSET];
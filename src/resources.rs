@@ -54,6 +54,7 @@ pub enum Image {
 
     ObjectRebar,
     ObjectRubble,
+    ObjectLamp,
 
     SquaddieFront,
     SquaddieLeft,
@@ -63,11 +64,33 @@ pub enum Image {
     // todo: machine left and right
     MachineFront,
     MachineBack,
-
-    Ruin1Left,
-    Ruin1Top,
-    Ruin2Left,
-    Ruin2Top,
+    TurretFront,
+    TurretBack,
+
+    BrickLeft,
+    BrickTop,
+    ConcreteLeft,
+    ConcreteTop,
+    MetalSheetLeft,
+    MetalSheetTop,
+    WoodenFenceLeft,
+    WoodenFenceTop,
+
+    BrickLeftCracked,
+    BrickTopCracked,
+    ConcreteLeftCracked,
+    ConcreteTopCracked,
+    MetalSheetLeftCracked,
+    MetalSheetTopCracked,
+    WoodenFenceLeftCracked,
+    WoodenFenceTopCracked,
+
+    DoorLeft,
+    DoorTop,
+    WindowLeft,
+    WindowTop,
+    LowWallLeft,
+    LowWallTop,
 
     PitTop,
     PitLeft,
@@ -89,10 +112,22 @@ pub enum Image {
     AmmoClip,
     Bandages,
     Grenade,
+    Intel,
+    Flare,
+    LitFlare,
+    SmokeGrenade,
+    Smoke,
+    Turret,
+    IncendiaryGrenade,
+    Fire1,
+    Fire2,
+    Fire3,
 
     Cursor,
     CursorCrosshair,
     Path,
+    NoiseMarker,
+    DownedMarker,
 
     // todo: the button image (and general ui) could use some work
     Button,
@@ -106,6 +141,7 @@ pub enum Image {
     Explosion1,
     Explosion2,
     Explosion3,
+    Scorch,
 
     Title,
 }
@@ -131,46 +167,84 @@ impl ImageSource for Image {
             Image::PitBL => tiles!(4, 1, 1, 1),
             Image::PitBR => tiles!(5, 1, 1, 1),
 
+            Image::ObjectLamp => tiles!(6, 1, 1, 1),
+
             Image::SquaddieFront => tiles!(0, 2, 1, 1),
             Image::SquaddieLeft => tiles!(1, 2, 1, 1),
             Image::SquaddieBack => tiles!(2, 2, 1, 1),
             Image::SquaddieRight => tiles!(3, 2, 1, 1),
             Image::MachineFront => tiles!(4, 2, 1, 1),
             Image::MachineBack => tiles!(5, 2, 1, 1),
-
-            Image::Ruin1Left => tiles!(0, 3, 1, 1),
-            Image::Ruin1Top => tiles!(1, 3, 1, 1),
-            Image::Ruin2Left => tiles!(2, 3, 1, 1),
-            Image::Ruin2Top => tiles!(3, 3, 1, 1),
+            Image::TurretFront => tiles!(6, 2, 1, 1),
+            Image::TurretBack => tiles!(7, 2, 1, 1),
+
+            Image::BrickLeft => tiles!(0, 3, 1, 1),
+            Image::BrickTop => tiles!(1, 3, 1, 1),
+            Image::ConcreteLeft => tiles!(2, 3, 1, 1),
+            Image::ConcreteTop => tiles!(3, 3, 1, 1),
+            Image::MetalSheetLeft => tiles!(4, 3, 1, 1),
+            Image::MetalSheetTop => tiles!(5, 3, 1, 1),
+            Image::WoodenFenceLeft => tiles!(6, 3, 1, 1),
+            Image::WoodenFenceTop => tiles!(7, 3, 1, 1),
 
             Image::RegularBullet => tiles!(0, 4, 1, 1),
             Image::PlasmaBullet => tiles!(1, 4, 1, 1),
 
-            Image::SquaddieCorpse => tiles!(0, 5, 1, 1),
-            Image::MachineCorpse => tiles!(1, 5, 1, 1),
-            Image::Scrap => tiles!(2, 5, 1, 1),
-            Image::Weapon => tiles!(3, 5, 1, 1),
-            Image::AmmoClip => tiles!(4, 5, 1, 1),
-            Image::Bandages => tiles!(5, 5, 1, 1),
-            Image::Grenade => tiles!(6, 5, 1, 1),
-
-            Image::Cursor => tiles!(0, 6, 1, 1),
-            Image::CursorCrosshair => tiles!(1, 6, 1, 1),
-            Image::Path => tiles!(2, 6, 1, 1),
-
-            Image::LeftEdge => tiles!(0, 7, 1, 1),
-            Image::RightEdge => tiles!(1, 7, 1, 1),
-            Image::Skeleton => tiles!(2, 7, 1, 1),
-            Image::SkeletonCracked => tiles!(3, 7, 1, 1),
-            Image::Rubble => tiles!(4, 7, 1, 1),
-            Image::Crater => tiles!(5, 7, 1, 1),
-            Image::Explosion1 => tiles!(6, 7, 1, 1),
-            Image::Explosion2 => tiles!(7, 7, 1, 1),
-            Image::Explosion3 => tiles!(8, 7, 1, 1),
-
-            Image::Title => tiles!(0, 8, 10, 1),
-
-            Image::Button => tiles!(0, 9, 1, 0.5),
+            Image::BrickLeftCracked => tiles!(2, 4, 1, 1),
+            Image::BrickTopCracked => tiles!(3, 4, 1, 1),
+            Image::ConcreteLeftCracked => tiles!(4, 4, 1, 1),
+            Image::ConcreteTopCracked => tiles!(5, 4, 1, 1),
+            Image::MetalSheetLeftCracked => tiles!(6, 4, 1, 1),
+            Image::MetalSheetTopCracked => tiles!(7, 4, 1, 1),
+            Image::WoodenFenceLeftCracked => tiles!(8, 4, 1, 1),
+            Image::WoodenFenceTopCracked => tiles!(9, 4, 1, 1),
+
+            Image::DoorLeft => tiles!(0, 5, 1, 1),
+            Image::DoorTop => tiles!(1, 5, 1, 1),
+            Image::WindowLeft => tiles!(2, 5, 1, 1),
+            Image::WindowTop => tiles!(3, 5, 1, 1),
+            Image::LowWallLeft => tiles!(4, 5, 1, 1),
+            Image::LowWallTop => tiles!(5, 5, 1, 1),
+
+            Image::SquaddieCorpse => tiles!(0, 6, 1, 1),
+            Image::MachineCorpse => tiles!(1, 6, 1, 1),
+            Image::Scrap => tiles!(2, 6, 1, 1),
+            Image::Weapon => tiles!(3, 6, 1, 1),
+            Image::AmmoClip => tiles!(4, 6, 1, 1),
+            Image::Bandages => tiles!(5, 6, 1, 1),
+            Image::Grenade => tiles!(6, 6, 1, 1),
+            Image::Intel => tiles!(7, 6, 1, 1),
+            Image::Flare => tiles!(8, 6, 1, 1),
+            Image::LitFlare => tiles!(9, 6, 1, 1),
+
+            Image::SmokeGrenade => tiles!(0, 7, 1, 1),
+            Image::Smoke => tiles!(1, 7, 1, 1),
+            Image::Turret => tiles!(2, 7, 1, 1),
+            Image::IncendiaryGrenade => tiles!(3, 7, 1, 1),
+            Image::Fire1 => tiles!(4, 7, 1, 1),
+            Image::Fire2 => tiles!(5, 7, 1, 1),
+            Image::Fire3 => tiles!(6, 7, 1, 1),
+
+            Image::Cursor => tiles!(0, 8, 1, 1),
+            Image::CursorCrosshair => tiles!(1, 8, 1, 1),
+            Image::Path => tiles!(2, 8, 1, 1),
+            Image::NoiseMarker => tiles!(3, 8, 1, 1),
+            Image::DownedMarker => tiles!(4, 8, 1, 1),
+
+            Image::LeftEdge => tiles!(0, 9, 1, 1),
+            Image::RightEdge => tiles!(1, 9, 1, 1),
+            Image::Skeleton => tiles!(2, 9, 1, 1),
+            Image::SkeletonCracked => tiles!(3, 9, 1, 1),
+            Image::Rubble => tiles!(4, 9, 1, 1),
+            Image::Crater => tiles!(5, 9, 1, 1),
+            Image::Explosion1 => tiles!(6, 9, 1, 1),
+            Image::Explosion2 => tiles!(7, 9, 1, 1),
+            Image::Explosion3 => tiles!(8, 9, 1, 1),
+            Image::Scorch => tiles!(9, 9, 1, 1),
+
+            Image::Title => tiles!(0, 10, 10, 1),
+
+            Image::Button => tiles!(0, 11, 1, 0.5),
         }
     }
 }
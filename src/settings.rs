@@ -1,7 +1,10 @@
 use std::fs::File;
 use std::io::Read;
 
-use battle::units::UnitType;
+use battle::loadouts::Loadout;
+use battle::map::{Arrival, Wave, Weather};
+use battle::objectives::ObjectiveType;
+use battle::units::{Side, UnitType};
 use networking::*;
 use utils::clamp;
 
@@ -168,6 +171,16 @@ pub struct SkirmishSettings {
     pub player_a_unit_type: UnitType,
     pub player_b_unit_type: UnitType,
     pub light: u8,
+    pub weather: Weather,
+    pub objective: ObjectiveType,
+    pub turn_limit: Option<u16>,
+    pub player_a_reinforcements: usize,
+    pub player_b_reinforcements: usize,
+    pub reinforcement_turn: u16,
+    pub reinforcement_arrival: Arrival,
+    pub player_a_loadouts: Vec<Loadout>,
+    pub player_b_loadouts: Vec<Loadout>,
+    pub loadout_budget: Option<u16>,
     pub game_type: GameType,
     pub address: String,
     pub save_game: Option<PathBuf>,
@@ -176,7 +189,7 @@ pub struct SkirmishSettings {
 // The default skirmish settings
 impl Default for SkirmishSettings {
     fn default() -> SkirmishSettings {
-        SkirmishSettings {
+        let mut settings = SkirmishSettings {
             width: 30,
             height: 30,
             player_a_units: 6,
@@ -184,16 +197,35 @@ impl Default for SkirmishSettings {
             player_a_unit_type: UnitType::Squaddie,
             player_b_unit_type: UnitType::Machine,
             light: 10,
+            weather: Weather::Clear,
+            objective: ObjectiveType::Annihilation,
+            turn_limit: None,
+            player_a_reinforcements: 0,
+            player_b_reinforcements: 0,
+            reinforcement_turn: 5,
+            reinforcement_arrival: Arrival::SpawnZone,
+            player_a_loadouts: Vec::new(),
+            player_b_loadouts: Vec::new(),
+            loadout_budget: None,
             game_type: GameType::Local,
             address: DEFAULT_ADDR.into(),
             save_game: None,
-        }
+        };
+
+        settings.clamp();
+        settings
     }
 }
 
 impl SkirmishSettings {
     const MIN_MAP_SIZE: usize = 10;
     const MAX_MAP_SIZE: usize = 60;
+    const TURN_LIMIT_CHANGE: u16 = 5;
+    const MAX_TURN_LIMIT: u16 = 100;
+    const MIN_REINFORCEMENT_TURN: u16 = 2;
+    const LOADOUT_BUDGET_CHANGE: u16 = 10;
+    const MIN_LOADOUT_BUDGET: u16 = 30;
+    const MAX_LOADOUT_BUDGET: u16 = 100;
 
     // Ensure that the settings are between their min and max values
     pub fn clamp(&mut self) {
@@ -201,14 +233,58 @@ impl SkirmishSettings {
         self.height = clamp(self.height, Self::MIN_MAP_SIZE, Self::MAX_MAP_SIZE);
         self.player_a_units = clamp(self.player_a_units, 1, self.width);
         self.player_b_units = clamp(self.player_b_units, 1, self.width);
+        self.player_a_reinforcements = clamp(self.player_a_reinforcements, 0, self.width);
+        self.player_b_reinforcements = clamp(self.player_b_reinforcements, 0, self.width);
+        self.reinforcement_turn = clamp(
+            self.reinforcement_turn,
+            Self::MIN_REINFORCEMENT_TURN,
+            Self::MAX_TURN_LIMIT,
+        );
+        self.loadout_budget = self
+            .loadout_budget
+            .map(|budget| clamp(budget, Self::MIN_LOADOUT_BUDGET, Self::MAX_LOADOUT_BUDGET));
+
+        // Make sure each unit has a loadout that suits it
+        fit_loadouts(
+            &mut self.player_a_loadouts,
+            self.player_a_units,
+            self.player_a_unit_type,
+            self.loadout_budget,
+        );
+        fit_loadouts(
+            &mut self.player_b_loadouts,
+            self.player_b_units,
+            self.player_b_unit_type,
+            self.loadout_budget,
+        );
         self.light = clamp(self.light, 0, 10);
+        self.turn_limit = self
+            .turn_limit
+            .map(|limit| clamp(limit, Self::TURN_LIMIT_CHANGE, Self::MAX_TURN_LIMIT));
+    }
+
+    // Increase the turn limit, starting from no limit
+    pub fn increase_turn_limit(&mut self) {
+        self.turn_limit = Some(
+            self.turn_limit
+                .map(|limit| limit + Self::TURN_LIMIT_CHANGE)
+                .unwrap_or(Self::TURN_LIMIT_CHANGE),
+        );
+    }
+
+    // Decrease the turn limit, removing it if it goes too low
+    pub fn decrease_turn_limit(&mut self) {
+        self.turn_limit = self
+            .turn_limit
+            .and_then(|limit| limit.checked_sub(Self::TURN_LIMIT_CHANGE))
+            .filter(|&limit| limit > 0);
     }
 
     // Switch the player unit type
     pub fn change_player_a_unit_type(&mut self) {
         self.player_a_unit_type = match self.player_a_unit_type {
             UnitType::Squaddie => UnitType::Machine,
-            UnitType::Machine => UnitType::Squaddie,
+            UnitType::Machine | UnitType::Turret => UnitType::Squaddie,
         }
     }
 
@@ -216,15 +292,79 @@ impl SkirmishSettings {
     pub fn change_player_b_unit_type(&mut self) {
         self.player_b_unit_type = match self.player_b_unit_type {
             UnitType::Squaddie => UnitType::Machine,
-            UnitType::Machine => UnitType::Squaddie,
+            UnitType::Machine | UnitType::Turret => UnitType::Squaddie,
         }
     }
 
+    // Increase the loadout budget, starting from no budget
+    pub fn increase_loadout_budget(&mut self) {
+        self.loadout_budget = Some(
+            self.loadout_budget
+                .map(|budget| budget + Self::LOADOUT_BUDGET_CHANGE)
+                .unwrap_or(Self::MIN_LOADOUT_BUDGET),
+        );
+    }
+
+    // Decrease the loadout budget, removing it if it goes too low
+    pub fn decrease_loadout_budget(&mut self) {
+        self.loadout_budget = self
+            .loadout_budget
+            .map(|budget| budget - Self::LOADOUT_BUDGET_CHANGE)
+            .filter(|&budget| budget >= Self::MIN_LOADOUT_BUDGET);
+    }
+
+    // Get the loadouts of a side and the type of unit they're for
+    pub fn loadouts_mut(&mut self, side: Side) -> (&mut Vec<Loadout>, UnitType) {
+        match side {
+            Side::PlayerA => (&mut self.player_a_loadouts, self.player_a_unit_type),
+            Side::PlayerB => (&mut self.player_b_loadouts, self.player_b_unit_type),
+        }
+    }
+
+    // Get the waves of reinforcements for each side
+    pub fn reinforcement_waves(&self) -> Vec<Wave> {
+        vec![
+            (
+                Side::PlayerA,
+                self.player_a_unit_type,
+                self.player_a_reinforcements,
+            ),
+            (
+                Side::PlayerB,
+                self.player_b_unit_type,
+                self.player_b_reinforcements,
+            ),
+        ]
+        .into_iter()
+        .filter(|&(_, _, count)| count > 0)
+        .map(|(side, tag, count)| Wave {
+            side,
+            tag,
+            count,
+            turn: self.reinforcement_turn,
+            arrival: self.reinforcement_arrival,
+        })
+        .collect()
+    }
+
     pub fn set_savegame(&mut self, savegame: &str, settings: &Settings) {
         self.save_game = Some(PathBuf::from(&settings.savegames).join(savegame));
     }
 }
 
+// Give a loadout to each unit of a side, fitting them to the unit type and budget
+fn fit_loadouts(loadouts: &mut Vec<Loadout>, units: usize, tag: UnitType, budget: Option<u16>) {
+    loadouts.truncate(units);
+
+    while loadouts.len() < units {
+        loadouts.push(Loadout::new(tag));
+    }
+
+    for loadout in loadouts {
+        loadout.fit(tag, budget);
+    }
+}
+
 #[test]
 fn load_save() {
     let mut settings = Settings::default();
@@ -1,5 +1,8 @@
 // The different weapons in the game
 
+use rand;
+use rand::Rng;
+
 use std::cmp::min;
 use std::fmt;
 
@@ -39,14 +42,32 @@ impl WeaponType {
         }
     }
 
-    pub fn damage(self) -> i16 {
+    // The lowest and highest damage a normal hit can do
+    pub fn damage_range(self) -> (i16, i16) {
         match self {
-            WeaponType::Rifle => 40,
-            WeaponType::MachineGun => 20,
-            WeaponType::PlasmaRifle => 60,
+            WeaponType::Rifle => (30, 50),
+            WeaponType::MachineGun => (15, 25),
+            WeaponType::PlasmaRifle => (50, 70),
         }
     }
 
+    // The average damage of a normal hit
+    pub fn damage(self) -> i16 {
+        let (low, high) = self.damage_range();
+        (low + high) / 2
+    }
+
+    // Roll the damage for a hit
+    pub fn roll_damage(self) -> i16 {
+        let (low, high) = self.damage_range();
+        rand::thread_rng().gen_range(low, high + 1)
+    }
+
+    // Critical hits do half as much again as the best normal hit
+    pub fn critical_damage(self) -> i16 {
+        self.damage_range().1 * 3 / 2
+    }
+
     pub fn capacity(self) -> u8 {
         match self {
             WeaponType::Rifle => 6,